@@ -1,6 +1,14 @@
-#![allow(unused, unused_comparisons, dead_code)]
+#![allow(
+    unused,
+    unused_comparisons,
+    dead_code,
+    clippy::needless_return,
+    clippy::manual_unwrap_or,
+    clippy::absurd_extreme_comparisons
+)]
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Team {
     Black,
     White,
@@ -17,7 +25,8 @@ impl std::ops::Not for Team {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Piece {
     King(RochadeAbility),
     Queen,
@@ -26,25 +35,28 @@ pub enum Piece {
     Horse,
     Pawn(EnPassanteVulnerability),
 }
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EnPassanteVulnerability {
     Vulnerable,
     Invulnerable,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RochadeAbility {
     Able,
     Unable,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PieceWithTeam {
     pub piece: Piece,
     pub team: Team,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct TilePosition {
     pub letter: usize,
     pub number: usize,
@@ -61,13 +73,38 @@ enum Direction {
     DownLeft,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RochadeSide {
+    KingSide,
+    QueenSide,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlayKind {
+    Quiet,
+    Capture,
+    DoublePush,
+    EnPassante,
+    Rochade(RochadeSide),
+    // the piece the pawn turns into, a promotion may also capture
+    Promotion(Piece),
+    // for variants: the piece is put onto `target`, `origin` is meaningless
+    Drop(Piece),
+}
+
+// deserialized in serialization.rs, where the kind and the captured piece are checked
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Play {
     pub origin: TilePosition,
     pub target: TilePosition,
+    pub kind: PlayKind,
+    pub captured: Option<PieceWithTeam>,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct GameState {
     pub turn: Team,
     pub board: [[Option<PieceWithTeam>; 8]; 8],
@@ -80,6 +117,16 @@ impl TilePosition {
         }
         return true;
     }
+    // the tile as it is seen, the same tile unless the board is flipped
+    pub fn oriented(self, flipped: bool) -> TilePosition {
+        if !flipped {
+            return self;
+        }
+        return TilePosition {
+            letter: 7 - self.letter,
+            number: 7 - self.number,
+        };
+    }
 }
 
 impl Play {
@@ -94,6 +141,43 @@ impl Play {
         return possible_plays;
     }
     pub fn get_possible_plays_for_tile(origin: TilePosition, game_state: &GameState) -> Vec<Play> {
+        let mut possible_plays = Self::get_pseudo_legal_plays_for_tile(origin, game_state);
+        possible_plays.retain(|play| !game_state.after(*play).is_in_check(game_state.turn));
+        return possible_plays;
+    }
+    pub fn is_capture(&self) -> bool {
+        return self.captured.is_some();
+    }
+    pub fn drop(piece: Piece, target: TilePosition) -> Self {
+        return Self {
+            origin: target,
+            target,
+            kind: PlayKind::Drop(piece),
+            captured: None,
+        };
+    }
+    fn with_promotions(play: Play, team: Team) -> Vec<Play> {
+        let last_number = match team {
+            Team::White => 7,
+            Team::Black => 0,
+        };
+        if play.target.number != last_number {
+            return vec![play];
+        }
+        return [
+            Piece::Queen,
+            Piece::Rook(RochadeAbility::Unable),
+            Piece::Bishop,
+            Piece::Horse,
+        ]
+        .into_iter()
+        .map(|piece| Play {
+            kind: PlayKind::Promotion(piece),
+            ..play
+        })
+        .collect();
+    }
+    fn get_pseudo_legal_plays_for_tile(origin: TilePosition, game_state: &GameState) -> Vec<Play> {
         let mut possible_plays: Vec<Play> = vec![];
         if !origin.is_valid() {
             //println!("invalid tile");
@@ -112,17 +196,18 @@ impl Play {
                     Team::White => 1,
                     Team::Black => -1,
                 };
-                if game_state.board[(origin.number as isize + direction_coefficient) as usize]
-                    [origin.letter]
-                    .is_none()
-                {
-                    possible_plays.push(Play {
-                        origin,
-                        target: TilePosition {
-                            letter: origin.letter,
-                            number: (origin.number as isize + direction_coefficient) as usize,
-                        },
-                    });
+                let forward = TilePosition {
+                    letter: origin.letter,
+                    number: (origin.number as isize + direction_coefficient) as usize,
+                };
+                if !forward.is_valid() {
+                    return possible_plays;
+                }
+                if game_state.board[forward.number][forward.letter].is_none() {
+                    possible_plays.append(&mut Self::with_promotions(
+                        game_state.play_to(origin, forward),
+                        piece_with_team.team,
+                    ));
                     if origin.number
                         == ((3.5 - 2.5 * direction_coefficient as f32).round() as usize)
                         && game_state.board
@@ -137,36 +222,55 @@ impl Play {
                                 number: (origin.number as isize + 2 * direction_coefficient)
                                     as usize,
                             },
+                            kind: PlayKind::DoublePush,
+                            captured: None,
                         });
                     }
                 }
-                let target = TilePosition {
-                    letter: match origin.letter.checked_sub(1) {
-                        Some(num) => num,
-                        None => 255,
+                let diagonal_targets = [
+                    TilePosition {
+                        letter: match origin.letter.checked_sub(1) {
+                            Some(num) => num,
+                            None => 255,
+                        },
+                        number: forward.number,
                     },
-                    number: (origin.number as isize + direction_coefficient) as usize,
-                };
-                if target.is_valid() {
+                    TilePosition {
+                        letter: origin.letter + 1,
+                        number: forward.number,
+                    },
+                ];
+                for target in diagonal_targets {
+                    if !target.is_valid() {
+                        continue;
+                    }
                     if let Some(target_piece_with_team) =
                         game_state.board[target.number][target.letter]
                     {
                         if target_piece_with_team.team == !piece_with_team.team {
-                            possible_plays.push(Play { origin, target });
+                            possible_plays.append(&mut Self::with_promotions(
+                                game_state.play_to(origin, target),
+                                piece_with_team.team,
+                            ));
                         }
+                        continue;
                     }
-                }
-                let target = TilePosition {
-                    letter: origin.letter + 1,
-                    number: (origin.number as isize + direction_coefficient) as usize,
-                };
-                if target.is_valid() {
-                    if let Some(target_piece_with_team) =
-                        game_state.board[target.number][target.letter]
+                    // the pawn passing by stands next to us, not on the target tile
+                    let Some(passed_piece_with_team) =
+                        game_state.board[origin.number][target.letter]
+                    else {
+                        continue;
+                    };
+                    if passed_piece_with_team.team == !piece_with_team.team
+                        && passed_piece_with_team.piece
+                            == Piece::Pawn(EnPassanteVulnerability::Vulnerable)
                     {
-                        if target_piece_with_team.team == !piece_with_team.team {
-                            possible_plays.push(Play { origin, target });
-                        }
+                        possible_plays.push(Play {
+                            origin,
+                            target,
+                            kind: PlayKind::EnPassante,
+                            captured: Some(passed_piece_with_team),
+                        });
                     }
                 }
             }
@@ -233,7 +337,15 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
+                    }
+                }
+
+                if piece_with_team.piece == Piece::King(RochadeAbility::Able) {
+                    for side in [RochadeSide::KingSide, RochadeSide::QueenSide] {
+                        if let Some(play) = game_state.rochade(origin, side) {
+                            possible_plays.push(play);
+                        }
                     }
                 }
             }
@@ -306,7 +418,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
             }
@@ -323,7 +435,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -339,7 +451,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -355,7 +467,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -371,7 +483,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
             }
@@ -388,7 +500,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -404,7 +516,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -420,7 +532,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -436,7 +548,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
             }
@@ -453,7 +565,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -469,7 +581,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -485,7 +597,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -501,7 +613,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -517,7 +629,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -533,7 +645,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -549,7 +661,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
 
@@ -565,7 +677,7 @@ impl Play {
                                 continue;
                             }
                         }
-                        possible_plays.push(Play { origin, target });
+                        possible_plays.push(game_state.play_to(origin, target));
                     }
                 }
             }
@@ -622,13 +734,234 @@ impl GameState {
         }
         return Ok(unblocked_tiles);
     }
+    fn play_to(&self, origin: TilePosition, target: TilePosition) -> Play {
+        let captured = self.board[target.number][target.letter];
+        return Play {
+            origin,
+            target,
+            kind: match captured {
+                Some(_) => PlayKind::Capture,
+                None => PlayKind::Quiet,
+            },
+            captured,
+        };
+    }
+    fn rochade(&self, origin: TilePosition, side: RochadeSide) -> Option<Play> {
+        let king = self.board[origin.number][origin.letter]?;
+        if origin.letter != 4 || king.piece != Piece::King(RochadeAbility::Able) {
+            return None;
+        }
+        let (rook_letter, step): (usize, isize) = match side {
+            RochadeSide::KingSide => (7, 1),
+            RochadeSide::QueenSide => (0, -1),
+        };
+        let rook = PieceWithTeam {
+            piece: Piece::Rook(RochadeAbility::Able),
+            team: king.team,
+        };
+        if self.board[origin.number][rook_letter] != Some(rook) {
+            return None;
+        }
+        let mut letter = origin.letter as isize + step;
+        while letter as usize != rook_letter {
+            if self.board[origin.number][letter as usize].is_some() {
+                return None;
+            }
+            letter += step;
+        }
+        // the king may not start on, pass through or land on an attacked tile
+        for i in 0..3 {
+            let tile = TilePosition {
+                letter: (origin.letter as isize + i * step) as usize,
+                number: origin.number,
+            };
+            if self.is_tile_attacked(tile, !king.team) {
+                return None;
+            }
+        }
+        return Some(Play {
+            origin,
+            target: TilePosition {
+                letter: (origin.letter as isize + 2 * step) as usize,
+                number: origin.number,
+            },
+            kind: PlayKind::Rochade(side),
+            captured: None,
+        });
+    }
+    pub fn find_king(&self, team: Team) -> Option<TilePosition> {
+        for (number, row) in self.board.iter().enumerate() {
+            for (letter, piece_option) in row.iter().enumerate() {
+                let Some(piece_with_team) = *piece_option else {
+                    continue;
+                };
+                if piece_with_team.team == team && matches!(piece_with_team.piece, Piece::King(_)) {
+                    return Some(TilePosition { letter, number });
+                }
+            }
+        }
+        return None;
+    }
+    pub fn is_in_check(&self, team: Team) -> bool {
+        let Some(king_position) = self.find_king(team) else {
+            return false;
+        };
+        return self.is_tile_attacked(king_position, !team);
+    }
+    pub fn is_tile_attacked(&self, tile: TilePosition, attacker: Team) -> bool {
+        let piece_at = |letter: isize, number: isize| -> Option<Piece> {
+            let position = TilePosition {
+                letter: letter as usize,
+                number: number as usize,
+            };
+            if !position.is_valid() {
+                return None;
+            }
+            match self.board[position.number][position.letter] {
+                Some(piece_with_team) if piece_with_team.team == attacker => {
+                    Some(piece_with_team.piece)
+                }
+                _ => None,
+            }
+        };
+        let letter = tile.letter as isize;
+        let number = tile.number as isize;
+
+        // pawns attack diagonally forward, so look diagonally backwards from the tile
+        let pawn_number = match attacker {
+            Team::White => number - 1,
+            Team::Black => number + 1,
+        };
+        for pawn_letter in [letter - 1, letter + 1] {
+            if let Some(Piece::Pawn(_)) = piece_at(pawn_letter, pawn_number) {
+                return true;
+            }
+        }
+        for (letter_offset, number_offset) in [
+            (1, 2),
+            (2, 1),
+            (2, -1),
+            (1, -2),
+            (-1, -2),
+            (-2, -1),
+            (-2, 1),
+            (-1, 2),
+        ] {
+            if let Some(Piece::Horse) = piece_at(letter + letter_offset, number + number_offset) {
+                return true;
+            }
+        }
+        for (letter_offset, number_offset) in [
+            (1, 1),
+            (1, 0),
+            (1, -1),
+            (0, -1),
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (0, 1),
+        ] {
+            if let Some(Piece::King(_)) = piece_at(letter + letter_offset, number + number_offset) {
+                return true;
+            }
+        }
+        for direction in [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+            Direction::UpRight,
+            Direction::UpLeft,
+            Direction::DownRight,
+            Direction::DownLeft,
+        ] {
+            let Ok(tiles) = self.unblocked_tiles_in_direction(tile, direction) else {
+                continue;
+            };
+            // the last unblocked tile is the blocking piece, if there is one
+            let Some(last) = tiles.last() else {
+                continue;
+            };
+            let Some(piece) = piece_at(last.letter as isize, last.number as isize) else {
+                continue;
+            };
+            let diagonal = matches!(
+                direction,
+                Direction::UpRight | Direction::UpLeft | Direction::DownRight | Direction::DownLeft
+            );
+            match piece {
+                Piece::Queen => return true,
+                Piece::Bishop if diagonal => return true,
+                Piece::Rook(_) if !diagonal => return true,
+                _ => {}
+            }
+        }
+        return false;
+    }
     pub fn after(&self, play: Play) -> Self {
         let mut next_game_state: GameState = *self;
         next_game_state.turn = !next_game_state.turn;
-        next_game_state.board[play.target.number][play.target.letter] =
-            self.board[play.origin.number][play.origin.letter];
+
+        // a pawn can only be taken en passante right after its double push
+        for row in next_game_state.board.iter_mut() {
+            for piece_option in row.iter_mut() {
+                if let Some(PieceWithTeam {
+                    piece: Piece::Pawn(vulnerability),
+                    ..
+                }) = piece_option
+                {
+                    *vulnerability = EnPassanteVulnerability::Invulnerable;
+                }
+            }
+        }
+
+        if let PlayKind::Drop(piece) = play.kind {
+            next_game_state.board[play.target.number][play.target.letter] = Some(PieceWithTeam {
+                piece,
+                team: self.turn,
+            });
+            return next_game_state;
+        }
+
+        let moved = self.board[play.origin.number][play.origin.letter].map(|piece_with_team| {
+            let piece = match (piece_with_team.piece, play.kind) {
+                (_, PlayKind::Promotion(piece)) => piece,
+                (Piece::King(_), _) => Piece::King(RochadeAbility::Unable),
+                (Piece::Rook(_), _) => Piece::Rook(RochadeAbility::Unable),
+                (Piece::Pawn(_), PlayKind::DoublePush) => {
+                    Piece::Pawn(EnPassanteVulnerability::Vulnerable)
+                }
+                (Piece::Pawn(_), _) => Piece::Pawn(EnPassanteVulnerability::Invulnerable),
+                (piece, _) => piece,
+            };
+            PieceWithTeam {
+                piece,
+                team: piece_with_team.team,
+            }
+        });
+        next_game_state.board[play.target.number][play.target.letter] = moved;
         next_game_state.board[play.origin.number][play.origin.letter] = None;
 
+        match play.kind {
+            PlayKind::EnPassante => {
+                next_game_state.board[play.origin.number][play.target.letter] = None;
+            }
+            PlayKind::Rochade(side) => {
+                let (rook_origin_letter, rook_target_letter) = match side {
+                    RochadeSide::KingSide => (7, 5),
+                    RochadeSide::QueenSide => (0, 3),
+                };
+                let rook = next_game_state.board[play.origin.number][rook_origin_letter];
+                next_game_state.board[play.origin.number][rook_origin_letter] = None;
+                next_game_state.board[play.origin.number][rook_target_letter] =
+                    rook.map(|rook| PieceWithTeam {
+                        piece: Piece::Rook(RochadeAbility::Unable),
+                        team: rook.team,
+                    });
+            }
+            _ => {}
+        }
+
         return next_game_state;
     }
     pub fn empty() -> Self {
@@ -794,3 +1127,9 @@ impl GameState {
         };
     }
 }
+
+impl Default for GameState {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
@@ -0,0 +1,767 @@
+#![allow(clippy::needless_return)]
+
+use crate::exit_with_usage;
+use deep_red::chess::{GameState, Play, TilePosition};
+use deep_red::epd::{Epd, EpdOperation};
+use deep_red::game::{Annotation, Game, GameResult};
+use deep_red::perft;
+use deep_red::pgn::read_games;
+use deep_red::selfplay::{self, Adjudication, Engine, MatchScore, Sprt, SprtVerdict};
+use deep_red::solver::{self, SearchLimits, MATE};
+use deep_red::suite;
+use deep_red::svg::{PieceSet, SvgOptions};
+use deep_red::terminal::{self, format_evaluation, DisplayOptions};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+// the subcommands besides the window, each gets the arguments after its name
+
+pub fn terminal(args: &[String]) {
+    let mut options = DisplayOptions::default();
+    for arg in args {
+        match arg.as_str() {
+            "--unicode" => options.unicode = true,
+            "--colors" => options.colors = true,
+            "--no-coordinates" => options.coordinates = false,
+            _ => exit_with_usage(&format!("unknown option {}", arg)),
+        }
+    }
+    let stdin = std::io::stdin();
+    if let Err(error) = terminal::run(stdin.lock(), std::io::stdout(), options) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+// prints the diagram, the last of the plays is highlighted
+pub fn svg(args: &[String]) {
+    let mut game_state = GameState::new();
+    let mut options = SvgOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(value) => value.as_str(),
+            None => exit_with_usage(&format!("{} needs a value", arg)),
+        };
+        match arg.as_str() {
+            "--fen" => {
+                game_state = GameState::from_fen(value())
+                    .unwrap_or_else(|error| exit_with_usage(&error.to_string()))
+            }
+            "--moves" => {
+                for notation in value().split_whitespace() {
+                    let play = Play::from_notation(notation, &game_state)
+                        .unwrap_or_else(|error| exit_with_usage(&error.to_string()));
+                    game_state = game_state.after(play);
+                    options.highlights = vec![play.origin, play.target];
+                }
+            }
+            "--size" => {
+                options.size = value()
+                    .parse()
+                    .unwrap_or_else(|_| exit_with_usage("size must be a number of pixels"))
+            }
+            "--flip" => options.flipped = true,
+            "--no-coordinates" => options.coordinates = false,
+            "--pieces" => {
+                options.pieces = match value() {
+                    "embedded" => PieceSet::Embedded,
+                    "unicode" => PieceSet::Unicode,
+                    directory => PieceSet::Directory(directory.into()),
+                }
+            }
+            "--highlight" => options.highlights.extend(tiles(value())),
+            "--annotations" => {
+                for command in value().split(',') {
+                    let annotation = Annotation::from_command(command)
+                        .unwrap_or_else(|error| exit_with_usage(&error.to_string()));
+                    options.annotations.push(annotation);
+                }
+            }
+            _ => exit_with_usage(&format!("unknown option {}", arg)),
+        }
+    }
+    match game_state.to_svg(&options) {
+        Ok(svg) => print!("{}", svg),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(feature = "render")]
+pub fn gif(args: &[String]) {
+    use deep_red::game::Game;
+    use deep_red::render::GifOptions;
+    use std::time::Duration;
+
+    let (Some(input), Some(output)) = (args.first(), args.get(1)) else {
+        exit_with_usage("gif needs a pgn file and the file to write");
+    };
+    let mut options = GifOptions::default();
+    let mut args = args[2..].iter();
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(value) => value.as_str(),
+            None => exit_with_usage(&format!("{} needs a value", arg)),
+        };
+        let mut milliseconds = |name: &str| {
+            let milliseconds = value().parse().unwrap_or_else(|_| {
+                exit_with_usage(&format!("{} must be a number of milliseconds", name))
+            });
+            Duration::from_millis(milliseconds)
+        };
+        match arg.as_str() {
+            "--size" => {
+                options.size = value()
+                    .parse()
+                    .unwrap_or_else(|_| exit_with_usage("size must be a number of pixels"))
+            }
+            "--delay" => options.delay = milliseconds("delay"),
+            "--hold" => options.final_hold = milliseconds("hold"),
+            "--flip" => options.flipped = true,
+            "--no-coordinates" => options.coordinates = false,
+            "--no-highlights" => options.highlight_last_play = false,
+            _ => exit_with_usage(&format!("unknown option {}", arg)),
+        }
+    }
+    let written = std::fs::read_to_string(input)
+        .map_err(|error| error.to_string())
+        .and_then(|pgn| Game::from_pgn(&pgn).map_err(|error| error.to_string()))
+        .and_then(|game| game.to_gif(&options).map_err(|error| error.to_string()))
+        .and_then(|gif| std::fs::write(output, gif).map_err(|error| error.to_string()));
+    if let Err(error) = written {
+        eprintln!("{}: {}", input, error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "render"))]
+pub fn gif(_args: &[String]) {
+    eprintln!("deep_red was built without the render feature");
+}
+
+// the number of play sequences, with --divide for every first play
+pub fn perft(args: &[String]) {
+    let game_state = position(args.first());
+    let Some(depth) = args.get(1).and_then(|depth| depth.parse().ok()) else {
+        exit_with_usage("perft needs a position and a depth");
+    };
+    let mut divide = false;
+    for arg in &args[2..] {
+        match arg.as_str() {
+            "--divide" => divide = true,
+            _ => exit_with_usage(&format!("unknown option {}", arg)),
+        }
+    }
+    let start = Instant::now();
+    let nodes = match divide {
+        true => {
+            let mut nodes = 0;
+            for (play, count) in perft::divide(&game_state, depth) {
+                println!("{} {}", play.to_uci(), count);
+                nodes += count;
+            }
+            nodes
+        }
+        false => perft::perft(&game_state, depth),
+    };
+    println!("{}", nodes);
+    eprintln!("{:.3} seconds", start.elapsed().as_secs_f64());
+}
+
+// searches like the solver in the window, the answer is written like uci engines do
+pub fn bestmove(args: &[String]) {
+    let game_state = position(args.first());
+    let limits = search_limits(args.get(1..).unwrap_or_default());
+    let result = solver::search(&game_state, limits);
+    println!(
+        "info depth {} score {} nodes {}",
+        result.depth,
+        uci_score(result.evaluation),
+        result.nodes
+    );
+    match result.play {
+        Some(play) => println!("bestmove {}", play.to_uci()),
+        None => println!("bestmove (none)"),
+    }
+}
+
+// every position of the file is searched and compared to its bm and am operations
+pub fn epd_suite(args: &[String]) {
+    let Some(file) = args.first() else {
+        exit_with_usage("epd-suite needs an epd file");
+    };
+    let limits = search_limits(&args[1..]);
+    let text = std::fs::read_to_string(file).unwrap_or_else(|error| {
+        eprintln!("{}: {}", file, error);
+        std::process::exit(1);
+    });
+
+    let start = Instant::now();
+    let mut score = suite::SuiteScore::default();
+    let mut nodes = 0;
+    for (line, result) in suite::solve_all(&text, limits) {
+        score.add(&result);
+        let (epd, result) = match result {
+            Ok(result) => result,
+            Err(error) => {
+                println!("line {}: {}", line, error);
+                continue;
+            }
+        };
+        let game_state = &epd.game_state;
+        let sans = |plays: &[Play]| -> String {
+            let sans: Vec<String> = plays.iter().map(|play| play.to_san(game_state)).collect();
+            return sans.join(" ");
+        };
+        let mut expected = vec![];
+        if !result.best_plays.is_empty() {
+            expected.push(format!("bm {}", sans(&result.best_plays)));
+        }
+        if !result.avoid_plays.is_empty() {
+            expected.push(format!("am {}", sans(&result.avoid_plays)));
+        }
+        let verdict = match result.solved {
+            true => "solved",
+            false => "failed",
+        };
+        let play = match result.search.play {
+            Some(play) => play.to_san(game_state),
+            None => "-".to_string(),
+        };
+        let mut report = format!(
+            "{}  {}  {}  {}  depth {}  {}  {:.2}s",
+            result
+                .id
+                .clone()
+                .unwrap_or_else(|| format!("line {}", line)),
+            verdict,
+            play,
+            expected.join(", "),
+            result.search.depth,
+            format_evaluation(result.search.evaluation),
+            result.time.as_secs_f64()
+        );
+        if let Some(comment) = &result.comment {
+            report += &format!("  \"{}\"", comment);
+        }
+        println!("{}", report);
+        nodes += result.search.nodes;
+    }
+    println!(
+        "{} positions, {} solved, {} failed, {} errors",
+        score.positions(),
+        score.solved,
+        score.failed,
+        score.errors
+    );
+    println!(
+        "{:.2} seconds, {} nodes",
+        start.elapsed().as_secs_f64(),
+        nodes
+    );
+}
+
+// "cp 35" or "mate -2", from the view of the team to move
+fn uci_score(evaluation: i32) -> String {
+    if evaluation.abs() > MATE - 1000 {
+        let moves = (MATE - evaluation.abs() + 1) / 2;
+        return format!("mate {}", moves * evaluation.signum());
+    }
+    return format!("cp {}", evaluation);
+}
+
+// the evaluation without searching, from the view of the team to move
+pub fn eval(args: &[String]) {
+    let game_state = position(args.first());
+    if args.len() > 1 {
+        exit_with_usage(&format!("unknown option {}", args[1]));
+    }
+    println!("{}", format_evaluation(solver::evaluate(&game_state)));
+}
+
+// a png, or an svg if the file ends with .svg
+pub fn render(args: &[String]) {
+    let game_state = position(args.first());
+    let mut output: Option<String> = None;
+    let mut options = SvgOptions::default();
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(value) => value.as_str(),
+            None => exit_with_usage(&format!("{} needs a value", arg)),
+        };
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(value().to_string()),
+            "--size" => {
+                options.size = value()
+                    .parse()
+                    .unwrap_or_else(|_| exit_with_usage("size must be a number of pixels"))
+            }
+            "--flip" => options.flipped = true,
+            "--no-coordinates" => options.coordinates = false,
+            "--highlight" => options.highlights.extend(tiles(value())),
+            _ => exit_with_usage(&format!("unknown option {}", arg)),
+        }
+    }
+    let Some(output) = output else {
+        exit_with_usage("render needs -o FILE");
+    };
+    let written = match output.ends_with(".svg") {
+        true => game_state
+            .to_svg(&options)
+            .and_then(|svg| std::fs::write(&output, svg))
+            .map_err(|error| error.to_string()),
+        false => write_png(&game_state, &options, &output),
+    };
+    if let Err(error) = written {
+        eprintln!("{}: {}", output, error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(feature = "render")]
+fn write_png(game_state: &GameState, options: &SvgOptions, output: &str) -> Result<(), String> {
+    use deep_red::render::{render_png, RenderOptions};
+
+    let render_options = RenderOptions {
+        flipped: options.flipped,
+        coordinates: options.coordinates,
+        highlights: options.highlights.clone(),
+    };
+    let png =
+        render_png(game_state, options.size, &render_options).map_err(|error| error.to_string())?;
+    return std::fs::write(output, png).map_err(|error| error.to_string());
+}
+
+#[cfg(not(feature = "render"))]
+fn write_png(_game_state: &GameState, _options: &SvgOptions, _output: &str) -> Result<(), String> {
+    return Err("deep_red was built without the render feature, only .svg works".to_string());
+}
+
+// reports every game that can not be read, fails if there is one
+pub fn pgn_validate(args: &[String]) {
+    let [file] = args else {
+        exit_with_usage("pgn-validate needs one pgn file");
+    };
+    let pgn = std::fs::read_to_string(file).unwrap_or_else(|error| {
+        eprintln!("{}: {}", file, error);
+        std::process::exit(1);
+    });
+    let games = read_games(&pgn);
+    let mut invalid = 0;
+    for (index, game) in games.iter().enumerate() {
+        if let Err(error) = game {
+            println!("game {}: {}", index + 1, error);
+            invalid += 1;
+        }
+    }
+    println!("{} games, {} invalid", games.len(), invalid);
+    if invalid > 0 {
+        std::process::exit(1);
+    }
+}
+
+// fen and epd have one position per line, pgn games end up in their last position
+pub fn convert(args: &[String]) {
+    let mut from = None;
+    let mut to = None;
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(value) => value.as_str(),
+            None => exit_with_usage(&format!("{} needs a value", arg)),
+        };
+        match arg.as_str() {
+            "--from" => from = Some(format(value())),
+            "--to" => to = Some(format(value())),
+            file if input.is_none() && !file.starts_with("--") => input = Some(file.to_string()),
+            _ => exit_with_usage(&format!("unknown option {}", arg)),
+        }
+    }
+    let (Some(from), Some(to)) = (from, to) else {
+        exit_with_usage("convert needs --from and --to");
+    };
+    let text = match &input {
+        Some(file) => std::fs::read_to_string(file),
+        None => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text).map(|_| text)
+        }
+    };
+    let text = text.unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+
+    // the epd operations are kept when converting from epd to epd
+    let mut records: Vec<Result<(Game, Epd), String>> = vec![];
+    match from {
+        Format::Pgn => {
+            for (index, game) in read_games(&text).into_iter().enumerate() {
+                records.push(
+                    game.map(|game| {
+                        let epd = epd_with_counters(&game);
+                        (game, epd)
+                    })
+                    .map_err(|error| format!("game {}: {}", index + 1, error)),
+                );
+            }
+        }
+        Format::Fen | Format::Epd => {
+            for (index, line) in text.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = match from {
+                    Format::Fen => Game::from_fen(line).map(|game| {
+                        let epd = epd_with_counters(&game);
+                        (game, epd)
+                    }),
+                    _ => Epd::from_epd(line)
+                        .and_then(|epd| Ok((Game::from_fen(&epd.to_fen())?, epd))),
+                };
+                records.push(record.map_err(|error| format!("line {}: {}", index + 1, error)));
+            }
+        }
+    }
+
+    let mut failed = false;
+    for record in records {
+        match (record, to) {
+            (Ok((game, _)), Format::Pgn) => println!("{}", game.to_pgn()),
+            (Ok((game, _)), Format::Fen) => println!("{}", game.to_fen()),
+            (Ok((_, epd)), Format::Epd) => println!("{}", epd.to_epd()),
+            (Err(error), _) => {
+                eprintln!("{}", error);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+// epd has no move counters, they are kept in the hmvc and fmvn operations
+fn epd_with_counters(game: &Game) -> Epd {
+    let mut epd = Epd::new(*game.game_state());
+    for (opcode, counter) in [
+        ("hmvc", game.halfmove_clock()),
+        ("fmvn", game.fullmove_number()),
+    ] {
+        epd.operations.push(EpdOperation {
+            opcode: opcode.to_string(),
+            operands: vec![counter.to_string()],
+        });
+    }
+    return epd;
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Fen,
+    Pgn,
+    Epd,
+}
+
+fn format(name: &str) -> Format {
+    match name {
+        "fen" => return Format::Fen,
+        "pgn" => return Format::Pgn,
+        "epd" => return Format::Epd,
+        _ => exit_with_usage(&format!("unknown format {}, try fen, pgn or epd", name)),
+    }
+}
+
+// --depth N and --time SECONDS, like for the solver in the window
+fn search_limits(args: &[String]) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut depth_given = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(value) => value.as_str(),
+            None => exit_with_usage(&format!("{} needs a value", arg)),
+        };
+        match arg.as_str() {
+            "--depth" => {
+                limits.depth = value()
+                    .parse()
+                    .unwrap_or_else(|_| exit_with_usage("depth must be a number"));
+                depth_given = true;
+            }
+            "--time" => {
+                let seconds = value().parse().ok();
+                let Some(time) =
+                    seconds.and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                else {
+                    exit_with_usage("time must be a number of seconds");
+                };
+                limits.time = Some(time);
+            }
+            _ => exit_with_usage(&format!("unknown option {}", arg)),
+        }
+    }
+    // with only a time limit the solver deepens until the time is up
+    if limits.time.is_some() && !depth_given {
+        limits.depth = 64;
+    }
+    return limits;
+}
+
+// a fen, or "startpos" for the usual starting position
+fn position(argument: Option<&String>) -> GameState {
+    match argument.map(|argument| argument.as_str()) {
+        Some("startpos") => return GameState::new(),
+        Some(fen) => {
+            return GameState::from_fen(fen)
+                .unwrap_or_else(|error| exit_with_usage(&error.to_string()))
+        }
+        None => exit_with_usage("a position is needed, a fen in quotes or startpos"),
+    }
+}
+
+// "e2,e4"
+fn tiles(list: &str) -> Vec<TilePosition> {
+    return list
+        .split(',')
+        .map(|tile| {
+            TilePosition::from_notation(tile)
+                .unwrap_or_else(|error| exit_with_usage(&error.to_string()))
+        })
+        .collect();
+}
+
+// many games between two engines from the openings, with alternating colors
+pub fn selfplay(args: &[String]) {
+    let mut first_limits = SearchLimits::default();
+    let mut second_limits = SearchLimits::default();
+    let mut first_program: Option<PathBuf> = None;
+    let mut second_program: Option<PathBuf> = None;
+    let mut openings = None;
+    let mut games = 100;
+    let mut concurrency = 1;
+    let mut sprt = None;
+    let mut pgn_file = None;
+    let mut adjudication = Adjudication::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(value) => value.as_str(),
+            None => exit_with_usage(&format!("{} needs a value", arg)),
+        };
+        let number = |value: &str, name: &str| -> usize {
+            match value.parse() {
+                Ok(number) if number > 0 => return number,
+                _ => exit_with_usage(&format!("{} must be a positive number", name)),
+            }
+        };
+        match arg.as_str() {
+            "--first" => first_limits = engine_limits(value()),
+            "--second" => second_limits = engine_limits(value()),
+            "--first-program" => first_program = Some(value().into()),
+            "--second-program" => second_program = Some(value().into()),
+            "--openings" => openings = Some(read_openings(value())),
+            "--games" => games = number(value(), "games"),
+            "--concurrency" => concurrency = number(value(), "concurrency"),
+            "--sprt" => {
+                let value = value();
+                let bounds = value
+                    .split_once(',')
+                    .and_then(|(elo0, elo1)| Some((elo0.parse().ok()?, elo1.parse().ok()?)));
+                let Some((elo0, elo1)) = bounds else {
+                    exit_with_usage("sprt must look like 0,10");
+                };
+                sprt = Some(Sprt {
+                    elo0,
+                    elo1,
+                    ..Sprt::default()
+                });
+            }
+            "--pgn" => pgn_file = Some(value().to_string()),
+            "--max-plays" => adjudication.max_plays = number(value(), "max plays"),
+            "--no-adjudication" => {
+                adjudication.win_plays = usize::MAX;
+                adjudication.draw_plays = usize::MAX;
+            }
+            _ => exit_with_usage(&format!("unknown option {}", arg)),
+        }
+    }
+    let engine = |limits, program| match program {
+        Some(path) => Engine::Program { path, limits },
+        None => Engine::Solver(limits),
+    };
+    let first = engine(first_limits, first_program);
+    let second = engine(second_limits, second_program);
+    let openings = openings.unwrap_or_else(selfplay::default_openings);
+    if openings.is_empty() {
+        eprintln!("no openings to play");
+        std::process::exit(1);
+    }
+    println!("first: {}", first.name());
+    println!("second: {}", second.name());
+
+    // every opening is played twice, once with each engine as white
+    let next_game = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    let mut score = MatchScore::default();
+    let mut played: Vec<(usize, Game)> = vec![];
+    let mut failed = false;
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency {
+            let sender = sender.clone();
+            let (first, second, openings) = (&first, &second, &openings);
+            let (next_game, stop, adjudication) = (&next_game, &stop, &adjudication);
+            scope.spawn(move || loop {
+                let index = next_game.fetch_add(1, Ordering::SeqCst);
+                if index >= games || stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let opening = &openings[(index / 2) % openings.len()];
+                let result = match index % 2 {
+                    0 => selfplay::play_game(first, second, opening, adjudication),
+                    _ => selfplay::play_game(second, first, opening, adjudication),
+                };
+                if sender.send((index, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        for (index, result) in receiver {
+            let (game, ending) = match result {
+                Ok(result) => result,
+                Err(error) => {
+                    eprintln!("game {}: {}", index + 1, error);
+                    failed = true;
+                    stop.store(true, Ordering::SeqCst);
+                    continue;
+                }
+            };
+            let first_is_white = index % 2 == 0;
+            match (game.metadata.result, first_is_white) {
+                (GameResult::WhiteWins, true) | (GameResult::BlackWins, false) => score.wins += 1,
+                (GameResult::WhiteWins, false) | (GameResult::BlackWins, true) => score.losses += 1,
+                _ => score.draws += 1,
+            }
+            let mut report = format!(
+                "game {}: {} - {} {} {}, first +{} ={} -{}, elo {}",
+                index + 1,
+                game.metadata.white,
+                game.metadata.black,
+                game.metadata.result.to_pgn(),
+                ending.describe(),
+                score.wins,
+                score.draws,
+                score.losses,
+                format_elo(score.elo())
+            );
+            if let Some(sprt) = sprt {
+                report += &format!(", llr {:.2}", score.llr(sprt.elo0, sprt.elo1));
+                if sprt.verdict(&score) != SprtVerdict::Continue {
+                    stop.store(true, Ordering::SeqCst);
+                }
+            }
+            println!("{}", report);
+            played.push((index, game));
+        }
+    });
+
+    println!(
+        "{} games, first +{} ={} -{}, score {:.1}%, elo {}",
+        score.games(),
+        score.wins,
+        score.draws,
+        score.losses,
+        score.score() * 100.0,
+        format_elo(score.elo())
+    );
+    if let Some(sprt) = sprt {
+        let (lower, upper) = sprt.bounds();
+        let verdict = match sprt.verdict(&score) {
+            SprtVerdict::AcceptElo0 => format!("first is not {} elo better", sprt.elo0),
+            SprtVerdict::AcceptElo1 => format!("first is {} elo better", sprt.elo1),
+            SprtVerdict::Continue => "no verdict yet, play more games".to_string(),
+        };
+        println!(
+            "sprt elo0 {} elo1 {}: llr {:.2} ({:.2}, {:.2}), {}",
+            sprt.elo0,
+            sprt.elo1,
+            score.llr(sprt.elo0, sprt.elo1),
+            lower,
+            upper,
+            verdict
+        );
+    }
+    if let Some(pgn_file) = pgn_file {
+        played.sort_by_key(|(index, _)| *index);
+        let pgn: Vec<String> = played.iter().map(|(_, game)| game.to_pgn()).collect();
+        if let Err(error) = std::fs::write(&pgn_file, pgn.join("\n")) {
+            eprintln!("{}: {}", pgn_file, error);
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+// the margin is left out while it is unbounded, like after winning every game
+fn format_elo((elo, margin): (f64, f64)) -> String {
+    match margin.is_finite() {
+        true => return format!("{:+.1} ± {:.1}", elo, margin),
+        false => return format!("{:+.1}", elo),
+    }
+}
+
+// "depth=6", "time=0.5" or both separated by a comma
+fn engine_limits(text: &str) -> SearchLimits {
+    let mut args = vec![];
+    for setting in text.split(',') {
+        let Some((name, value)) = setting.split_once('=') else {
+            exit_with_usage(&format!("{} should look like depth=4 or time=0.5", setting));
+        };
+        args.push(format!("--{}", name));
+        args.push(value.to_string());
+    }
+    return search_limits(&args);
+}
+
+// the last positions of a pgn, otherwise a fen or epd per line
+fn read_openings(file: &str) -> Vec<GameState> {
+    let text = std::fs::read_to_string(file).unwrap_or_else(|error| {
+        eprintln!("{}: {}", file, error);
+        std::process::exit(1);
+    });
+    let mut openings = vec![];
+    if file.ends_with(".pgn") {
+        for (index, game) in read_games(&text).into_iter().enumerate() {
+            match game {
+                Ok(game) => openings.push(*game.game_state()),
+                Err(error) => eprintln!("{}: game {}: {}", file, index + 1, error),
+            }
+        }
+        return openings;
+    }
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let game_state =
+            GameState::from_fen(line).or_else(|_| Epd::from_epd(line).map(|epd| epd.game_state));
+        match game_state {
+            Ok(game_state) => openings.push(game_state),
+            Err(error) => eprintln!("{}: line {}: {}", file, index + 1, error),
+        }
+    }
+    return openings;
+}
@@ -0,0 +1,232 @@
+use crate::chess::*;
+use crate::notation::NotationError;
+
+// "bm Nf3;" or "id \"test 1\";", the opcode and what follows it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpdOperation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+// a position without the move counters of fen, followed by operations
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Epd {
+    pub game_state: GameState,
+    pub operations: Vec<EpdOperation>,
+}
+
+impl Epd {
+    pub fn new(game_state: GameState) -> Self {
+        return Self {
+            game_state,
+            operations: vec![],
+        };
+    }
+    pub fn from_epd(line: &str) -> Result<Self, NotationError> {
+        let line = line.trim();
+        // the four fields of fen up to the en passante tile
+        let mut rest = line;
+        let mut fields = vec![];
+        for _ in 0..4 {
+            rest = rest.trim_start();
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(NotationError::Epd(format!(
+                    "expected the four fields of the position in '{}'",
+                    line
+                )));
+            }
+            fields.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+        let game_state = GameState::from_fen(&fields.join(" "))?;
+
+        let mut operations = vec![];
+        let mut operation: Vec<String> = vec![];
+        let mut characters = rest.chars().peekable();
+        while let Some(character) = characters.next() {
+            match character {
+                ';' => {
+                    if !operation.is_empty() {
+                        let opcode = operation.remove(0);
+                        operations.push(EpdOperation {
+                            opcode,
+                            operands: std::mem::take(&mut operation),
+                        });
+                    }
+                }
+                '"' => {
+                    let mut operand = String::new();
+                    loop {
+                        match characters.next() {
+                            Some('"') => break,
+                            // \" and \\ stand for the character itself
+                            Some('\\') => match characters.next() {
+                                Some(character) => operand.push(character),
+                                None => {
+                                    return Err(NotationError::Epd(format!(
+                                        "unterminated string in '{}'",
+                                        line
+                                    )))
+                                }
+                            },
+                            Some(character) => operand.push(character),
+                            None => {
+                                return Err(NotationError::Epd(format!(
+                                    "unterminated string in '{}'",
+                                    line
+                                )))
+                            }
+                        }
+                    }
+                    operation.push(operand);
+                }
+                character if character.is_whitespace() => {}
+                character => {
+                    let mut word = character.to_string();
+                    while let Some(next) = characters.peek() {
+                        if next.is_whitespace() || *next == ';' {
+                            break;
+                        }
+                        word.push(*next);
+                        characters.next();
+                    }
+                    operation.push(word);
+                }
+            }
+        }
+        if !operation.is_empty() {
+            return Err(NotationError::Epd(format!(
+                "operation '{}' does not end with ';'",
+                operation.join(" ")
+            )));
+        }
+        return Ok(Self {
+            game_state,
+            operations,
+        });
+    }
+    pub fn to_epd(&self) -> String {
+        let fen = self.game_state.to_fen();
+        let fields: Vec<&str> = fen.split(' ').take(4).collect();
+        let mut epd = fields.join(" ");
+        for operation in &self.operations {
+            epd.push(' ');
+            epd += &operation.opcode;
+            for operand in &operation.operands {
+                // strings like the ones of id and c0 stay quoted
+                let quoted = operand.is_empty()
+                    || operand.contains(|c: char| c.is_whitespace() || c == ';' || c == '"')
+                    || operation.opcode == "id"
+                    || is_comment(&operation.opcode);
+                match quoted {
+                    true => {
+                        let escaped = operand.replace('\\', "\\\\").replace('"', "\\\"");
+                        epd += &format!(" \"{}\"", escaped);
+                    }
+                    false => epd += &format!(" {}", operand),
+                }
+            }
+            epd.push(';');
+        }
+        return epd;
+    }
+    // the fen with the move counters of the hmvc and fmvn operations, "0 1" without them
+    pub fn to_fen(&self) -> String {
+        let counter = |opcode: &str, default: &'static str| {
+            self.operation(opcode)
+                .and_then(|operands| operands.first())
+                .map_or(default, |operand| operand.as_str())
+                .to_string()
+        };
+        let fen = self.game_state.to_fen();
+        let fields: Vec<&str> = fen.split(' ').take(4).collect();
+        return format!(
+            "{} {} {}",
+            fields.join(" "),
+            counter("hmvc", "0"),
+            counter("fmvn", "1")
+        );
+    }
+    // the operands of the first operation with the opcode
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        return self
+            .operations
+            .iter()
+            .find(|operation| operation.opcode == opcode)
+            .map(|operation| operation.operands.as_slice());
+    }
+}
+
+// c0 to c9
+fn is_comment(opcode: &str) -> bool {
+    let mut characters = opcode.chars();
+    return characters.next() == Some('c')
+        && characters.next().is_some_and(|c| c.is_ascii_digit())
+        && characters.next().is_none();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITION: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -";
+
+    #[test]
+    fn position_fields() {
+        for position in [
+            POSITION,
+            "rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b Kq e3",
+            "8/8/8/8/8/8/8/K1k5 w - -",
+        ] {
+            let epd = Epd::from_epd(position).unwrap();
+            assert!(epd.operations.is_empty());
+            assert_eq!(epd.to_epd(), position);
+            assert_eq!(epd.game_state, GameState::from_fen(position).unwrap());
+        }
+    }
+
+    #[test]
+    fn operations() {
+        let line = format!(
+            "{} bm Qxh3 Nxf7; am Bxa6; id \"kiwipete 1\"; c0 \"fen with \\\"quotes\\\" and \\\\\"; hmvc 3; fmvn 12;",
+            POSITION
+        );
+        let epd = Epd::from_epd(&line).unwrap();
+        assert_eq!(
+            epd.operation("bm"),
+            Some(&["Qxh3".to_string(), "Nxf7".to_string()][..])
+        );
+        assert_eq!(epd.operation("am"), Some(&["Bxa6".to_string()][..]));
+        assert_eq!(epd.operation("id"), Some(&["kiwipete 1".to_string()][..]));
+        assert_eq!(
+            epd.operation("c0"),
+            Some(&["fen with \"quotes\" and \\".to_string()][..])
+        );
+        assert_eq!(epd.operation("pv"), None);
+        assert_eq!(epd.to_epd(), line);
+        assert_eq!(Epd::from_epd(&epd.to_epd()).unwrap(), epd);
+        assert_eq!(epd.to_fen(), format!("{} 3 12", POSITION));
+
+        // strings are quoted when written even if they were not in the line
+        let epd = Epd::from_epd(&format!("{} id single;c1 word ;", POSITION)).unwrap();
+        assert_eq!(
+            epd.to_epd(),
+            format!("{} id \"single\"; c1 \"word\";", POSITION)
+        );
+        assert_eq!(epd.to_fen(), format!("{} 0 1", POSITION));
+    }
+
+    #[test]
+    fn malformed() {
+        for line in [
+            format!("{} bm Qxh3", POSITION),
+            format!("{} bm Qxh3; c0 \"no end;", POSITION),
+            format!("{} c0 \"ends in an escape\\", POSITION),
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq".to_string(),
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP w KQkq - bm Qxh3;".to_string(),
+        ] {
+            assert!(Epd::from_epd(&line).is_err(), "{}", line);
+        }
+    }
+}
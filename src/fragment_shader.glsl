@@ -1,10 +1,12 @@
 #version 140
 
 in vec2 v_texture_position;
+in float v_opacity;
 out vec4 color;
 
 uniform sampler2D tex;
 
 void main() {
   color = texture(tex, v_texture_position);
+  color.a *= v_opacity;
 }
@@ -0,0 +1,467 @@
+use crate::chess::*;
+use crate::notation::NotationError;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Ongoing,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameMetadata {
+    pub white: String,
+    pub black: String,
+    pub result: GameResult,
+    // written like in pgn, "2023.03.18"
+    pub date: Option<String>,
+}
+
+// the colors of the usual chess programs, written G, R, Y and B in pgn
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnnotationColor {
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+
+// drawn on the board to explain a position
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Annotation {
+    Arrow {
+        origin: TilePosition,
+        target: TilePosition,
+        color: AnnotationColor,
+    },
+    Circle {
+        tile: TilePosition,
+        color: AnnotationColor,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PlayRecord {
+    pub play: Play,
+    pub san: String,
+    // the position after the play
+    pub game_state: GameState,
+    // drawn on the position after the play
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Game {
+    pub metadata: GameMetadata,
+    initial_game_state: GameState,
+    initial_annotations: Vec<Annotation>,
+    // the move counters of the fen the game started from
+    initial_halfmove_clock: u32,
+    initial_fullmove_number: u32,
+    plays: Vec<PlayRecord>,
+    // how many of the plays are currently made, the rest can be redone
+    ply: usize,
+}
+
+impl GameResult {
+    pub fn to_pgn(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Ongoing => "*",
+        }
+    }
+    pub fn from_pgn(result: &str) -> Option<Self> {
+        match result {
+            "1-0" => return Some(GameResult::WhiteWins),
+            "0-1" => return Some(GameResult::BlackWins),
+            "1/2-1/2" => return Some(GameResult::Draw),
+            "*" => return Some(GameResult::Ongoing),
+            _ => return None,
+        }
+    }
+    // the result if the game ended in this position
+    pub fn of(game_state: &GameState) -> Self {
+        if !Play::get_all_possible_plays(game_state).is_empty() {
+            return GameResult::Ongoing;
+        }
+        if !game_state.is_in_check(game_state.turn) {
+            return GameResult::Draw;
+        }
+        match game_state.turn {
+            Team::White => return GameResult::BlackWins,
+            Team::Black => return GameResult::WhiteWins,
+        }
+    }
+}
+
+impl Annotation {
+    // an arrow from and to the same tile would be the circle
+    pub fn tiles(&self) -> (TilePosition, TilePosition) {
+        match *self {
+            Annotation::Arrow { origin, target, .. } => return (origin, target),
+            Annotation::Circle { tile, .. } => return (tile, tile),
+        }
+    }
+    pub fn color(&self) -> AnnotationColor {
+        match *self {
+            Annotation::Arrow { color, .. } | Annotation::Circle { color, .. } => return color,
+        }
+    }
+}
+
+impl Default for GameMetadata {
+    fn default() -> Self {
+        return Self {
+            white: "?".to_string(),
+            black: "?".to_string(),
+            result: GameResult::Ongoing,
+            date: Some(today()),
+        };
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        return Self::from_game_state(GameState::new());
+    }
+    pub fn from_game_state(game_state: GameState) -> Self {
+        return Self {
+            metadata: GameMetadata::default(),
+            initial_game_state: game_state,
+            initial_annotations: vec![],
+            initial_halfmove_clock: 0,
+            initial_fullmove_number: 1,
+            plays: vec![],
+            ply: 0,
+        };
+    }
+    // keeps the move counters, which the game state alone throws away
+    pub fn from_fen(fen: &str) -> Result<Self, NotationError> {
+        let mut game = Self::from_game_state(GameState::from_fen(fen)?);
+        (game.initial_halfmove_clock, game.initial_fullmove_number) =
+            GameState::counters_from_fen(fen)?;
+        return Ok(game);
+    }
+    pub fn initial_fullmove_number(&self) -> u32 {
+        return self.initial_fullmove_number;
+    }
+    pub fn initial_fen(&self) -> String {
+        return self
+            .initial_game_state
+            .to_fen_with_counters(self.initial_halfmove_clock, self.initial_fullmove_number);
+    }
+    pub fn to_fen(&self) -> String {
+        return self
+            .game_state()
+            .to_fen_with_counters(self.halfmove_clock(), self.fullmove_number());
+    }
+    // the plays since the last capture or pawn move
+    pub fn halfmove_clock(&self) -> u32 {
+        let mut halfmove_clock = 0;
+        for record in self.plays[..self.ply].iter().rev() {
+            let pawn_moved = match record.play.kind {
+                PlayKind::DoublePush | PlayKind::EnPassante | PlayKind::Promotion(_) => true,
+                _ => matches!(
+                    record.game_state.board[record.play.target.number][record.play.target.letter],
+                    Some(PieceWithTeam {
+                        piece: Piece::Pawn(_),
+                        ..
+                    })
+                ),
+            };
+            if pawn_moved || record.play.is_capture() {
+                return halfmove_clock;
+            }
+            halfmove_clock += 1;
+        }
+        return self.initial_halfmove_clock + halfmove_clock;
+    }
+    // starts at 1 and goes up after every play of black
+    pub fn fullmove_number(&self) -> u32 {
+        let black_plays = match self.initial_game_state.turn {
+            Team::White => self.ply / 2,
+            Team::Black => self.ply.div_ceil(2),
+        };
+        return self.initial_fullmove_number + black_plays as u32;
+    }
+    pub fn initial_game_state(&self) -> &GameState {
+        return &self.initial_game_state;
+    }
+    pub fn game_state(&self) -> &GameState {
+        match self.ply {
+            0 => return &self.initial_game_state,
+            ply => return &self.plays[ply - 1].game_state,
+        }
+    }
+    // all recorded plays, including the undone ones after `ply`
+    pub fn plays(&self) -> &[PlayRecord] {
+        return &self.plays;
+    }
+    pub fn ply(&self) -> usize {
+        return self.ply;
+    }
+    pub fn last_play(&self) -> Option<&PlayRecord> {
+        match self.ply {
+            0 => return None,
+            ply => return self.plays.get(ply - 1),
+        }
+    }
+    pub fn play(&mut self, play: Play) {
+        // playing the play that would be redone keeps the rest of the record
+        if self.plays.get(self.ply).map(|record| record.play) == Some(play) {
+            self.set_ply(self.ply + 1);
+            return;
+        }
+        let game_state = *self.game_state();
+        self.plays.truncate(self.ply);
+        self.plays.push(PlayRecord {
+            play,
+            san: play.to_san(&game_state),
+            game_state: game_state.after(play),
+            annotations: vec![],
+        });
+        self.set_ply(self.ply + 1);
+    }
+    // the result follows the position, pgn sets a resignation again after the plays
+    fn set_ply(&mut self, ply: usize) {
+        self.ply = ply;
+        self.metadata.result = GameResult::of(self.game_state());
+    }
+    // the annotations of the position after `ply` plays
+    pub fn annotations_at(&self, ply: usize) -> &[Annotation] {
+        match ply {
+            0 => return &self.initial_annotations,
+            ply => match self.plays.get(ply - 1) {
+                Some(record) => return &record.annotations,
+                None => return &[],
+            },
+        }
+    }
+    pub fn annotations(&self) -> &[Annotation] {
+        return self.annotations_at(self.ply);
+    }
+    fn annotations_mut(&mut self) -> &mut Vec<Annotation> {
+        match self.ply {
+            0 => return &mut self.initial_annotations,
+            ply => return &mut self.plays[ply - 1].annotations,
+        }
+    }
+    pub fn set_annotations(&mut self, annotations: Vec<Annotation>) {
+        *self.annotations_mut() = annotations;
+    }
+    // drawing the same annotation again removes it, on the same tiles in another color replaces it
+    pub fn toggle_annotation(&mut self, annotation: Annotation) {
+        let annotations = self.annotations_mut();
+        match annotations
+            .iter()
+            .position(|other| other.tiles() == annotation.tiles())
+        {
+            Some(index) if annotations[index] == annotation => {
+                annotations.remove(index);
+            }
+            Some(index) => annotations[index] = annotation,
+            None => annotations.push(annotation),
+        }
+    }
+    pub fn undo(&mut self) -> bool {
+        if self.ply == 0 {
+            return false;
+        }
+        self.set_ply(self.ply - 1);
+        return true;
+    }
+    pub fn redo(&mut self) -> bool {
+        if self.ply == self.plays.len() {
+            return false;
+        }
+        self.set_ply(self.ply + 1);
+        return true;
+    }
+    pub fn go_to(&mut self, ply: usize) -> bool {
+        if ply > self.plays.len() {
+            return false;
+        }
+        self.set_ply(ply);
+        return true;
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 86_400)
+        .unwrap_or(0) as i64;
+    // days since 1970 to the civil calendar, after Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return format!("{:04}.{:02}.{:02}", year, month, day);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, san: &str) {
+        game.play(Play::from_san(san, game.game_state()).unwrap());
+    }
+
+    fn sans(game: &Game) -> Vec<&str> {
+        return game
+            .plays()
+            .iter()
+            .map(|record| record.san.as_str())
+            .collect();
+    }
+
+    fn tile(notation: &str) -> TilePosition {
+        return TilePosition::from_notation(notation).unwrap();
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut game = Game::new();
+        assert!(!game.undo());
+        play(&mut game, "e4");
+        play(&mut game, "e5");
+        assert!(game.undo());
+        assert_eq!(game.ply(), 1);
+        assert_eq!(game.last_play().unwrap().san, "e4");
+        assert!(game.undo());
+        assert!(!game.undo());
+        assert_eq!(*game.game_state(), GameState::new());
+        assert!(game.redo());
+        assert!(game.redo());
+        assert!(!game.redo());
+        assert_eq!(game.ply(), 2);
+        assert_eq!(game.game_state().turn, Team::White);
+    }
+
+    #[test]
+    fn divergent_play() {
+        let mut game = Game::new();
+        play(&mut game, "e4");
+        play(&mut game, "e5");
+        play(&mut game, "Nf3");
+        game.go_to(1);
+        // the play that would be redone keeps the rest
+        play(&mut game, "e5");
+        assert_eq!(sans(&game), ["e4", "e5", "Nf3"]);
+        assert_eq!(game.ply(), 2);
+        // another one throws it away
+        game.undo();
+        play(&mut game, "c5");
+        assert_eq!(sans(&game), ["e4", "c5"]);
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn go_to() {
+        let mut game = Game::new();
+        play(&mut game, "d4");
+        play(&mut game, "d5");
+        assert!(!game.go_to(3));
+        assert_eq!(game.ply(), 2);
+        assert!(game.go_to(0));
+        assert_eq!(*game.game_state(), GameState::new());
+        assert!(game.go_to(2));
+        assert_eq!(game.game_state(), &game.plays()[1].game_state);
+    }
+
+    #[test]
+    fn result_follows_the_ply() {
+        let mut game = Game::new();
+        for san in ["f3", "e5", "g4", "Qh4"] {
+            play(&mut game, san);
+        }
+        assert_eq!(game.metadata.result, GameResult::BlackWins);
+        game.undo();
+        assert_eq!(game.metadata.result, GameResult::Ongoing);
+        game.redo();
+        assert_eq!(game.metadata.result, GameResult::BlackWins);
+        game.go_to(0);
+        assert_eq!(game.metadata.result, GameResult::Ongoing);
+        game.go_to(4);
+        assert_eq!(game.metadata.result, GameResult::BlackWins);
+        game.go_to(3);
+        play(&mut game, "Qh4");
+        assert_eq!(game.metadata.result, GameResult::BlackWins);
+    }
+
+    #[test]
+    fn counters_with_black_to_move() {
+        let mut game_state =
+            GameState::new().after(Play::from_san("e4", &GameState::new()).unwrap());
+        game_state = game_state.after(Play::from_san("e5", &game_state).unwrap());
+        game_state = game_state.after(Play::from_san("Nf3", &game_state).unwrap());
+        let mut game = Game::from_game_state(game_state);
+        assert_eq!((game.halfmove_clock(), game.fullmove_number()), (0, 1));
+        play(&mut game, "Nc6");
+        assert_eq!((game.halfmove_clock(), game.fullmove_number()), (1, 2));
+        play(&mut game, "Bb5");
+        assert_eq!((game.halfmove_clock(), game.fullmove_number()), (2, 2));
+        play(&mut game, "a6");
+        assert_eq!((game.halfmove_clock(), game.fullmove_number()), (0, 3));
+        play(&mut game, "Bxc6");
+        assert_eq!((game.halfmove_clock(), game.fullmove_number()), (0, 3));
+        game.go_to(0);
+        assert_eq!((game.halfmove_clock(), game.fullmove_number()), (0, 1));
+    }
+
+    #[test]
+    fn toggle_annotation() {
+        let arrow = |color| Annotation::Arrow {
+            origin: tile("g1"),
+            target: tile("f3"),
+            color,
+        };
+        let circle = Annotation::Circle {
+            tile: tile("e4"),
+            color: AnnotationColor::Red,
+        };
+        let mut game = Game::new();
+        game.toggle_annotation(arrow(AnnotationColor::Green));
+        game.toggle_annotation(circle);
+        assert_eq!(game.annotations(), [arrow(AnnotationColor::Green), circle]);
+        // the same tiles in another color replace the arrow
+        game.toggle_annotation(arrow(AnnotationColor::Blue));
+        assert_eq!(game.annotations(), [arrow(AnnotationColor::Blue), circle]);
+        // the same annotation again removes it
+        game.toggle_annotation(arrow(AnnotationColor::Blue));
+        assert_eq!(game.annotations(), [circle]);
+
+        // every position has its own annotations
+        play(&mut game, "e4");
+        assert!(game.annotations().is_empty());
+        game.toggle_annotation(arrow(AnnotationColor::Yellow));
+        assert_eq!(game.annotations_at(0), [circle]);
+        assert_eq!(game.annotations_at(1), [arrow(AnnotationColor::Yellow)]);
+        game.undo();
+        assert_eq!(game.annotations(), [circle]);
+    }
+}
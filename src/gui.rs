@@ -0,0 +1,1047 @@
+#![allow(clippy::needless_return)]
+
+mod animation;
+mod annotations;
+mod atlas;
+mod clock;
+mod editor;
+mod sidebar;
+mod text;
+mod theme;
+
+pub use clock::TimeControl;
+pub use theme::{BoardColors, Theme, BOARD_COLORS};
+
+use animation::Animation;
+use clock::ChessClock;
+use deep_red::chess::*;
+use deep_red::game::{Annotation, AnnotationColor, Game, GameResult};
+use deep_red::pictures::{self, distinct_tiles};
+use deep_red::solver::{self, SearchLimits, SearchResult};
+use editor::{Editor, EditorAction};
+
+use deep_red::chess::PieceWithTeam;
+use glium::glutin;
+use glium::glutin::dpi::PhysicalPosition;
+use glium::{implement_vertex, uniform, Surface};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy)]
+struct Vertex {
+    position: [f32; 2],
+    texture_position: [f32; 2],
+}
+implement_vertex!(Vertex, position, texture_position);
+
+// one sprite, placed on the plane and cut out of the atlas
+#[derive(Clone, Copy)]
+struct Instance {
+    offset: [f32; 2],
+    scale: [f32; 2],
+    atlas_rect: [f32; 4],
+    opacity: f32,
+}
+implement_vertex!(Instance, offset, scale, atlas_rect, opacity);
+
+// where the plane goes in opengl coordinates, its middle and half its size
+#[derive(Clone, Copy)]
+struct Quad {
+    offset: [f32; 2],
+    scale: [f32; 2],
+}
+
+struct Shape {
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+}
+#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+enum PieceOrBoard {
+    Blue,
+    Red,
+    Board,
+    Piece(PieceWithTeam),
+}
+
+pub struct GuiOptions {
+    // the team the solver plays, both teams are human without it
+    pub engine: Option<Team>,
+    pub limits: SearchLimits,
+    pub clock: Option<TimeControl>,
+    // how long a piece takes from one tile to another, nothing is animated with zero
+    pub animation: Duration,
+    pub theme: Theme,
+}
+
+// sent from the solver thread once it has decided
+struct EngineResult {
+    game_state: GameState,
+    result: SearchResult,
+}
+
+// where the board and the sidebar are in the window, the board stays square
+// and both are centered together
+#[derive(Clone, Copy)]
+struct BoardLayout {
+    // physical pixels like the cursor positions, so the scale factor cancels out
+    window_size: glutin::dpi::PhysicalSize<u32>,
+    // black at the bottom
+    flipped: bool,
+}
+
+const TILE_SIZE: f32 = 0.25;
+// the sidebar right of the board is as wide as this many tiles
+const SIDEBAR_TILES: f64 = 3.0;
+const INITIAL_WINDOW_SIZE: glutin::dpi::LogicalSize<f64> =
+    glutin::dpi::LogicalSize::new(1100.0, 800.0);
+// how often the running clock is redrawn
+const CLOCK_REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+// the hover highlights are the blue ones, just lighter
+const HOVER_OPACITY: f32 = 0.35;
+
+pub fn run(options: GuiOptions) {
+    let mut game = Game::new();
+    /*game_state.board[3][3] = Some(PieceWithTeam {
+        piece: Piece::King(RochadeAbility::Able),
+        team: Team::White,
+    });*/
+
+    // nothing is selected until the first click on the board
+    let mut selected_tile: Option<TilePosition> = None;
+    let mut cursor_position: glutin::dpi::PhysicalPosition<f64> =
+        glutin::dpi::PhysicalPosition::new(0.0, 0.0);
+
+    let event_loop =
+        glutin::event_loop::EventLoopBuilder::<EngineResult>::with_user_event().build();
+    let event_loop_proxy = event_loop.create_proxy();
+    if options.engine == Some(game.game_state().turn) {
+        start_engine(*game.game_state(), options.limits, event_loop_proxy.clone());
+    }
+    let mut modifiers = glutin::event::ModifiersState::empty();
+    // the promotions to pick from while the picker is open
+    let mut promotion_choices: Vec<Play> = vec![];
+    let mut dragged_tile: Option<TilePosition> = None;
+    let mut cursor_in_window = false;
+    // where the right mouse button went down for an arrow or a circle
+    let mut annotation_origin: Option<TilePosition> = None;
+    let mut clock = options.clock.map(ChessClock::new);
+    // the team whose time ran out, nobody can play after that
+    let mut flagged: Option<Team> = None;
+    let mut evaluation: Option<(SearchResult, Team)> = None;
+    let mut animation: Option<Animation> = None;
+    // the board editor replaces the game while it is open
+    let mut editor: Option<Editor> = None;
+    let window_builder = glutin::window::WindowBuilder::new()
+        .with_resizable(true)
+        .with_title("deep_red")
+        .with_inner_size(INITIAL_WINDOW_SIZE)
+        .with_min_inner_size(glutin::dpi::LogicalSize::new(275.0, 200.0));
+    let context_builder = glutin::ContextBuilder::new();
+    let display = glium::Display::new(window_builder, context_builder, &event_loop).unwrap();
+    let mut board_layout = BoardLayout {
+        window_size: display.gl_window().window().inner_size(),
+        // a human playing black against the solver sees the board from black's side
+        flipped: options.engine == Some(Team::White),
+    };
+
+    let plane = Shape {
+        vertices: vec![
+            Vertex {
+                position: [-1.0, -1.0],
+                texture_position: [0.0, 0.0],
+            },
+            Vertex {
+                position: [1.0, -1.0],
+                texture_position: [1.0, 0.0],
+            },
+            Vertex {
+                position: [1.0, 1.0],
+                texture_position: [1.0, 1.0],
+            },
+            Vertex {
+                position: [-1.0, 1.0],
+                texture_position: [0.0, 1.0],
+            },
+        ],
+        indices: vec![0, 1, 2, 2, 3, 0],
+    };
+    let vertex_buffer = glium::VertexBuffer::new(&display, &plane.vertices).unwrap();
+    let indices = glium::IndexBuffer::new(
+        &display,
+        glium::index::PrimitiveType::TrianglesList,
+        &plane.indices,
+    )
+    .unwrap();
+
+    let program = glium::Program::from_source(
+        &display,
+        include_str!("vertex_shader.glsl"),
+        include_str!("fragment_shader.glsl"),
+        None,
+    )
+    .unwrap();
+    let text_renderer = text::TextRenderer::new(&display);
+    let annotation_renderer = annotations::AnnotationRenderer::new(&display);
+
+    let atlas = atlas::Atlas::new(&display, &options.theme.load());
+
+    let mut last_frame = Instant::now();
+
+    event_loop.run(move |ev, _, control_flow| {
+        let now = Instant::now();
+        // only animations need every frame
+        *control_flow = match (&animation, clock.as_ref().and_then(|clock| clock.running())) {
+            (Some(_), _) => glutin::event_loop::ControlFlow::Poll,
+            (None, Some(_)) => {
+                glutin::event_loop::ControlFlow::WaitUntil(now + CLOCK_REDRAW_INTERVAL)
+            }
+            (None, None) => glutin::event_loop::ControlFlow::Wait,
+        };
+        // a flag only falls while the game is still going on
+        let ongoing = GameResult::of(game.game_state()) == GameResult::Ongoing;
+        if let Some(team) = clock
+            .as_mut()
+            .filter(|_| ongoing)
+            .and_then(|clock| clock.flag(now))
+        {
+            flagged = Some(team);
+            game.metadata.result = match team {
+                Team::White => GameResult::BlackWins,
+                Team::Black => GameResult::WhiteWins,
+            };
+            promotion_choices.clear();
+            dragged_tile = None;
+        }
+
+        match ev {
+            glutin::event::Event::WindowEvent { event, .. } => match event {
+                glutin::event::WindowEvent::CloseRequested => {
+                    *control_flow = glutin::event_loop::ControlFlow::Exit;
+                    return;
+                }
+                glutin::event::WindowEvent::CursorMoved { position, .. } => {
+                    cursor_position = position;
+                    cursor_in_window = true;
+                }
+                glutin::event::WindowEvent::CursorLeft { .. } => {
+                    cursor_in_window = false;
+                }
+                glutin::event::WindowEvent::Resized(size) => {
+                    board_layout.window_size = size;
+                }
+                glutin::event::WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                    board_layout.window_size = *new_inner_size;
+                }
+                glutin::event::WindowEvent::MouseInput {
+                    state: glutin::event::ElementState::Pressed,
+                    button: glutin::event::MouseButton::Left,
+                    ..
+                } if editor.is_some() => {
+                    let Some(editing) = editor.as_mut() else {
+                        return;
+                    };
+                    let paused_clock = editing.paused_clock;
+                    match editing.click(&board_layout, &cursor_position) {
+                        Some(EditorAction::Start(game_state)) => {
+                            editor = None;
+                            game = Game::from_game_state(game_state);
+                            flagged = None;
+                            evaluation = None;
+                            clock = options.clock.map(ChessClock::new);
+                            if options.engine == Some(game.game_state().turn) {
+                                start_engine(
+                                    *game.game_state(),
+                                    options.limits,
+                                    event_loop_proxy.clone(),
+                                );
+                            }
+                        }
+                        Some(EditorAction::Cancel) => {
+                            editor = None;
+                            resume_game(&game, clock.as_mut(), paused_clock, now);
+                            if options.engine == Some(game.game_state().turn) && flagged.is_none() {
+                                start_engine(
+                                    *game.game_state(),
+                                    options.limits,
+                                    event_loop_proxy.clone(),
+                                );
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                glutin::event::WindowEvent::MouseInput { .. } if editor.is_some() => return,
+                glutin::event::WindowEvent::MouseInput { state, button, .. }
+                    if button == glutin::event::MouseButton::Left
+                        && state == glutin::event::ElementState::Pressed
+                        && options.engine != Some(game.game_state().turn)
+                        && flagged.is_none() =>
+                {
+                    let played = if !promotion_choices.is_empty() {
+                        // a click anywhere else cancels the promotion
+                        let choices = std::mem::take(&mut promotion_choices);
+                        let chosen =
+                            board_layout
+                                .get_selected_tile(&cursor_position)
+                                .and_then(|tile| {
+                                    promotion_tiles(&choices)
+                                        .iter()
+                                        .position(|choice_tile| *choice_tile == tile)
+                                });
+                        match chosen {
+                            Some(index) => {
+                                game.play(choices[index]);
+                                true
+                            }
+                            None => false,
+                        }
+                    } else {
+                        let previous_selected_tile = selected_tile;
+                        if let Some(tile) = board_layout.get_selected_tile(&cursor_position) {
+                            selected_tile = Some(tile);
+                        }
+                        let game_state = game.game_state();
+                        match (previous_selected_tile, selected_tile) {
+                            (_, Some(tile))
+                                if game_state.board[tile.number][tile.letter]
+                                    .is_some_and(|piece| piece.team == game_state.turn) =>
+                            {
+                                // picked up, it is played once it is dropped somewhere else
+                                dragged_tile = Some(tile);
+                                false
+                            }
+                            (Some(origin), Some(target)) => {
+                                try_play(origin, target, &mut game, &mut promotion_choices)
+                            }
+                            _ => false,
+                        }
+                    };
+                    if played {
+                        selected_tile = None;
+                        animation = game
+                            .last_play()
+                            .and_then(|record| Animation::new(record, options.animation));
+                        last_frame = now;
+                        press_clock(clock.as_mut(), &game, now);
+                    }
+                    if played && options.engine == Some(game.game_state().turn) {
+                        start_engine(*game.game_state(), options.limits, event_loop_proxy.clone());
+                    }
+                }
+                glutin::event::WindowEvent::MouseInput { state, button, .. }
+                    if button == glutin::event::MouseButton::Left
+                        && state == glutin::event::ElementState::Released =>
+                {
+                    let Some(origin) = dragged_tile.take() else {
+                        return;
+                    };
+                    // dropped on its own tile it stays selected for a second click,
+                    // an impossible drop just puts it back
+                    let played = match board_layout.get_selected_tile(&cursor_position) {
+                        Some(target) if target != origin => {
+                            try_play(origin, target, &mut game, &mut promotion_choices)
+                        }
+                        _ => false,
+                    };
+                    if played {
+                        // the piece is already where it was dropped
+                        selected_tile = None;
+                        animation = None;
+                        press_clock(clock.as_mut(), &game, now);
+                    }
+                    if played && options.engine == Some(game.game_state().turn) {
+                        start_engine(*game.game_state(), options.limits, event_loop_proxy.clone());
+                    }
+                }
+                glutin::event::WindowEvent::MouseInput {
+                    state,
+                    button: glutin::event::MouseButton::Right,
+                    ..
+                } => {
+                    let tile = board_layout.get_selected_tile(&cursor_position);
+                    match state {
+                        glutin::event::ElementState::Pressed => annotation_origin = tile,
+                        glutin::event::ElementState::Released => {
+                            let (Some(origin), Some(target)) = (annotation_origin.take(), tile)
+                            else {
+                                return;
+                            };
+                            game.toggle_annotation(annotation(
+                                origin,
+                                target,
+                                annotation_color(modifiers),
+                            ));
+                        }
+                    }
+                }
+                glutin::event::WindowEvent::ModifiersChanged(state) => {
+                    modifiers = state;
+                    return;
+                }
+                glutin::event::WindowEvent::KeyboardInput {
+                    input:
+                        glutin::event::KeyboardInput {
+                            state: glutin::event::ElementState::Pressed,
+                            virtual_keycode: Some(glutin::event::VirtualKeyCode::E),
+                            ..
+                        },
+                    ..
+                } => match editor.take() {
+                    Some(editing) => {
+                        resume_game(&game, clock.as_mut(), editing.paused_clock, now);
+                        if options.engine == Some(game.game_state().turn) && flagged.is_none() {
+                            start_engine(
+                                *game.game_state(),
+                                options.limits,
+                                event_loop_proxy.clone(),
+                            );
+                        }
+                    }
+                    None => {
+                        let paused_clock = clock.as_mut().is_some_and(|clock| clock.pause(now));
+                        editor = Some(Editor::new(*game.game_state(), paused_clock));
+                        // whatever was selected is gone once the editor closes again
+                        selected_tile = None;
+                        promotion_choices.clear();
+                        dragged_tile = None;
+                        annotation_origin = None;
+                        animation = None;
+                    }
+                },
+                // only flipping the board still works in the editor
+                glutin::event::WindowEvent::KeyboardInput { input, .. }
+                    if editor.is_some()
+                        && input.virtual_keycode != Some(glutin::event::VirtualKeyCode::F) =>
+                {
+                    return;
+                }
+                glutin::event::WindowEvent::KeyboardInput {
+                    input:
+                        glutin::event::KeyboardInput {
+                            state: glutin::event::ElementState::Pressed,
+                            virtual_keycode:
+                                Some(
+                                    glutin::event::VirtualKeyCode::Delete
+                                    | glutin::event::VirtualKeyCode::Back,
+                                ),
+                            ..
+                        },
+                    ..
+                } => {
+                    game.set_annotations(vec![]);
+                }
+                glutin::event::WindowEvent::KeyboardInput {
+                    input:
+                        glutin::event::KeyboardInput {
+                            state: glutin::event::ElementState::Pressed,
+                            virtual_keycode: Some(glutin::event::VirtualKeyCode::P),
+                            ..
+                        },
+                    ..
+                } => {
+                    print!("{}", game.to_pgn());
+                    return;
+                }
+                glutin::event::WindowEvent::KeyboardInput {
+                    input:
+                        glutin::event::KeyboardInput {
+                            state: glutin::event::ElementState::Pressed,
+                            virtual_keycode: Some(glutin::event::VirtualKeyCode::F),
+                            ..
+                        },
+                    ..
+                } => {
+                    board_layout.flipped = !board_layout.flipped;
+                }
+                glutin::event::WindowEvent::KeyboardInput {
+                    input:
+                        glutin::event::KeyboardInput {
+                            state: glutin::event::ElementState::Pressed,
+                            virtual_keycode: Some(key),
+                            ..
+                        },
+                    ..
+                } => {
+                    let moved = match key {
+                        glutin::event::VirtualKeyCode::Left => undo(&mut game, options.engine),
+                        glutin::event::VirtualKeyCode::Z if modifiers.ctrl() => {
+                            undo(&mut game, options.engine)
+                        }
+                        glutin::event::VirtualKeyCode::Right => redo(&mut game, options.engine),
+                        glutin::event::VirtualKeyCode::Y if modifiers.ctrl() => {
+                            redo(&mut game, options.engine)
+                        }
+                        glutin::event::VirtualKeyCode::Home => game.go_to(0),
+                        glutin::event::VirtualKeyCode::End => game.go_to(game.plays().len()),
+                        _ => return,
+                    };
+                    if !moved {
+                        return;
+                    }
+                    selected_tile = None;
+                    promotion_choices.clear();
+                    dragged_tile = None;
+                    animation = None;
+                    if let Some(clock) = clock.as_mut() {
+                        match GameResult::of(game.game_state()) {
+                            GameResult::Ongoing => clock.hand_over(game.game_state().turn, now),
+                            _ => {
+                                clock.pause(now);
+                            }
+                        }
+                    }
+                    if options.engine == Some(game.game_state().turn) && flagged.is_none() {
+                        start_engine(*game.game_state(), options.limits, event_loop_proxy.clone());
+                    }
+                }
+                _ => return,
+            },
+            glutin::event::Event::UserEvent(engine_result) => {
+                // the position may have changed while the solver was thinking, the
+                // solver starts again once the editor is closed
+                if engine_result.game_state != *game.game_state()
+                    || flagged.is_some()
+                    || editor.is_some()
+                {
+                    return;
+                }
+                let Some(play) = engine_result.result.play else {
+                    return;
+                };
+                evaluation = Some((engine_result.result, engine_result.game_state.turn));
+                game.play(play);
+                selected_tile = None;
+                animation = game
+                    .last_play()
+                    .and_then(|record| Animation::new(record, options.animation));
+                last_frame = now;
+                press_clock(clock.as_mut(), &game, now);
+            }
+            _ => (),
+        }
+        //println!("{},{}", cursor_position.x, cursor_position.y);
+        let frames_delta_time = now - last_frame;
+        last_frame = now;
+        if let Some(running) = animation.as_mut() {
+            if !running.advance(frames_delta_time) {
+                animation = None;
+            }
+        }
+        let game_state = match &editor {
+            Some(editing) => editing.game_state,
+            None => *game.game_state(),
+        };
+        // every sprite is one instance, they are drawn in this order
+        let mut instances: Vec<Instance> = vec![];
+        let mut add = |sprite: PieceOrBoard, quad: Quad, opacity: f32| {
+            instances.push(Instance {
+                offset: quad.offset,
+                scale: quad.scale,
+                atlas_rect: atlas.rect(sprite),
+                opacity,
+            });
+        };
+        add(PieceOrBoard::Board, board_layout.board_quad(), 1.0);
+
+        // the editor shows only the pieces
+        if editor.is_none() {
+            let mut highlighted_tiles: Vec<TilePosition> = vec![];
+            if let Some(record) = game.last_play() {
+                highlighted_tiles.push(record.play.origin);
+                highlighted_tiles.push(record.play.target);
+            }
+            if let Some(tile) = selected_tile {
+                match game_state.board[tile.number][tile.letter] {
+                    Some(piece) if piece.team == game_state.turn => highlighted_tiles.push(tile),
+                    _ => {}
+                }
+            }
+            if game_state.is_in_check(game_state.turn) {
+                if let Some(king_position) = game_state.find_king(game_state.turn) {
+                    highlighted_tiles.push(king_position);
+                }
+            }
+            for tile in distinct_tiles(&highlighted_tiles) {
+                add(PieceOrBoard::Red, board_layout.tile_quad(tile), 1.0);
+            }
+
+            if let Some(tile) = selected_tile {
+                for play in Play::get_possible_plays_for_tile(tile, &game_state) {
+                    add(PieceOrBoard::Blue, board_layout.tile_quad(play.target), 1.0);
+                }
+            }
+        }
+
+        let hovered_tile = match cursor_in_window && dragged_tile.is_none() {
+            true => board_layout.get_selected_tile(&cursor_position),
+            false => None,
+        };
+        if let Some(tile) = hovered_tile {
+            add(
+                PieceOrBoard::Blue,
+                board_layout.tile_quad(tile),
+                HOVER_OPACITY,
+            );
+            // where the hovered piece could go, if it may play now
+            let may_play = options.engine != Some(game_state.turn)
+                && flagged.is_none()
+                && editor.is_none()
+                && promotion_choices.is_empty()
+                && Some(tile) != selected_tile;
+            match game_state.board[tile.number][tile.letter] {
+                Some(piece) if piece.team == game_state.turn && may_play => {
+                    for play in Play::get_possible_plays_for_tile(tile, &game_state) {
+                        add(
+                            PieceOrBoard::Blue,
+                            board_layout.tile_quad(play.target),
+                            HOVER_OPACITY,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // the taken piece fades out below the one taking it
+        let progress = animation.as_ref().map_or(1.0, |running| running.progress());
+        if let Some((tile, piece_with_team)) =
+            animation.as_ref().and_then(|running| running.captured())
+        {
+            add(
+                PieceOrBoard::Piece(piece_with_team),
+                board_layout.tile_quad(tile),
+                1.0 - progress,
+            );
+        }
+
+        // moving pieces come after the others so they pass above them
+        let mut moving_tiles = vec![];
+        for (number, row) in game_state.board.iter().enumerate() {
+            for (letter, piece_option) in row.iter().enumerate() {
+                let tile = TilePosition { letter, number };
+                if dragged_tile == Some(tile) {
+                    continue;
+                }
+                if let Some(origin) = animation
+                    .as_ref()
+                    .and_then(|running| running.origin_of(tile))
+                {
+                    moving_tiles.push((origin, tile));
+                    continue;
+                }
+                let Some(piece_with_team) = *piece_option else {
+                    continue;
+                };
+                add(
+                    PieceOrBoard::Piece(piece_with_team),
+                    board_layout.tile_quad(tile),
+                    1.0,
+                );
+            }
+        }
+        for (origin, tile) in moving_tiles {
+            let Some(piece_with_team) = game_state.board[tile.number][tile.letter] else {
+                continue;
+            };
+            add(
+                PieceOrBoard::Piece(piece_with_team),
+                board_layout.moving_tile_quad(origin, tile, progress),
+                1.0,
+            );
+        }
+
+        // the dragged piece is drawn last so it is above the others
+        if let Some(tile) = dragged_tile {
+            if let Some(piece_with_team) = game_state.board[tile.number][tile.letter] {
+                add(
+                    PieceOrBoard::Piece(piece_with_team),
+                    board_layout.cursor_quad(&cursor_position),
+                    1.0,
+                );
+            }
+        }
+
+        for (play, tile) in promotion_choices
+            .iter()
+            .zip(promotion_tiles(&promotion_choices))
+        {
+            let PlayKind::Promotion(piece) = play.kind else {
+                continue;
+            };
+            let piece_with_team = PieceWithTeam {
+                piece,
+                team: game_state.turn,
+            };
+            // twice, so the pieces below are hidden a bit more
+            for sprite in [
+                PieceOrBoard::Blue,
+                PieceOrBoard::Blue,
+                PieceOrBoard::Piece(piece_with_team),
+            ] {
+                add(sprite, board_layout.tile_quad(tile), 1.0);
+            }
+        }
+
+        if let Some(editing) = &editor {
+            for (sprite, quad) in editing.sprites(&board_layout) {
+                add(sprite, quad, 1.0);
+            }
+        }
+
+        let instance_buffer = glium::VertexBuffer::new(&display, &instances).unwrap();
+        let uniforms = uniform! {
+            tex: atlas
+                .texture
+                .sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Linear),
+        };
+        let draw_parameters = glium::DrawParameters {
+            blend: glium::draw_parameters::Blend::alpha_blending(),
+            ..glium::draw_parameters::DrawParameters::default()
+        };
+        let mut target = display.draw();
+        target.clear_color(0.0, 0.0, 0.0, 1.0);
+        // the palette is drawn above the editor's background
+        if let Some(editing) = &editor {
+            editing.draw_panel(&mut target, &text_renderer, &board_layout);
+        }
+        target
+            .draw(
+                (&vertex_buffer, instance_buffer.per_instance().unwrap()),
+                &indices,
+                &program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+        // the arrow being drawn is shown before it is added
+        let mut annotations = match editor {
+            Some(_) => vec![],
+            None => game.annotations().to_vec(),
+        };
+        if let (Some(origin), Some(target)) = (
+            annotation_origin,
+            board_layout.get_selected_tile(&cursor_position),
+        ) {
+            let preview = annotation(origin, target, annotation_color(modifiers));
+            if !annotations.contains(&preview) {
+                annotations.push(preview);
+            }
+        }
+        annotation_renderer.draw(&mut target, &display, &board_layout, &annotations);
+        draw_coordinates(&mut target, &text_renderer, &board_layout);
+
+        if editor.is_none() {
+            sidebar::draw_sidebar(
+                &mut target,
+                &text_renderer,
+                &board_layout,
+                &game,
+                sidebar::SidebarState {
+                    clock: clock.as_ref(),
+                    flagged,
+                    engine: options.engine,
+                    evaluation,
+                },
+                now,
+            );
+        }
+
+        target.finish().unwrap();
+    });
+}
+/*
+struct Position {
+    x: f64,
+    y: f64,
+}
+impl Position {
+    fn as_glium_physical(&self) -> PhysicalPosition<f64> {}
+    fn as_board_indices(&self) -> (usize, usize) {}
+    fn as_gl(&self) -> (f32, f32) {}
+}*/
+impl BoardLayout {
+    fn board_size(&self) -> f64 {
+        let width = self.window_size.width as f64 * 8.0 / (8.0 + SIDEBAR_TILES);
+        return width.min(self.window_size.height as f64).max(1.0);
+    }
+    // the top left corner of the board in pixels
+    fn board_origin(&self) -> (f64, f64) {
+        let board_size = self.board_size();
+        return (
+            (self.window_size.width as f64 - board_size * (8.0 + SIDEBAR_TILES) / 8.0) * 0.5,
+            (self.window_size.height as f64 - board_size) * 0.5,
+        );
+    }
+    // left, top, width and height in pixels
+    fn sidebar_rect(&self) -> [f32; 4] {
+        let board_size = self.board_size();
+        let (x, y) = self.board_origin();
+        return [
+            (x + board_size) as f32,
+            y as f32,
+            (board_size * SIDEBAR_TILES / 8.0) as f32,
+            board_size as f32,
+        ];
+    }
+    // half the board's width and height in opengl coordinates
+    fn board_scale(&self) -> (f32, f32) {
+        let board_size = self.board_size();
+        return (
+            (board_size / self.window_size.width.max(1) as f64) as f32,
+            (board_size / self.window_size.height.max(1) as f64) as f32,
+        );
+    }
+    // the middle of the board in opengl coordinates
+    fn board_center(&self) -> (f32, f32) {
+        let board_size = self.board_size();
+        let (x, y) = self.board_origin();
+        return (
+            ((x + board_size * 0.5) / self.window_size.width.max(1) as f64 * 2.0 - 1.0) as f32,
+            (1.0 - (y + board_size * 0.5) / self.window_size.height.max(1) as f64 * 2.0) as f32,
+        );
+    }
+    fn board_quad(&self) -> Quad {
+        let (scale_x, scale_y) = self.board_scale();
+        let (center_x, center_y) = self.board_center();
+        return Quad {
+            offset: [center_x, center_y],
+            scale: [scale_x, scale_y],
+        };
+    }
+    // the middle of the tile in pixels
+    fn tile_center(&self, tile: TilePosition) -> [f32; 2] {
+        let tile_size = self.board_size() / 8.0;
+        let (origin_x, origin_y) = self.board_origin();
+        let tile = tile.oriented(self.flipped);
+        return [
+            (origin_x + (tile.letter as f64 + 0.5) * tile_size) as f32,
+            (origin_y + (7.5 - tile.number as f64) * tile_size) as f32,
+        ];
+    }
+    fn tile_quad(&self, tile: TilePosition) -> Quad {
+        let tile = tile.oriented(self.flipped);
+        return self.screen_tile_quad(tile.letter as f32, tile.number as f32);
+    }
+    // `progress` of the way from the origin to the target
+    fn moving_tile_quad(&self, origin: TilePosition, target: TilePosition, progress: f32) -> Quad {
+        let origin = origin.oriented(self.flipped);
+        let target = target.oriented(self.flipped);
+        return self.screen_tile_quad(
+            origin.letter as f32 + (target.letter as f32 - origin.letter as f32) * progress,
+            origin.number as f32 + (target.number as f32 - origin.number as f32) * progress,
+        );
+    }
+    // letter and number as seen on the screen, they can be between tiles
+    fn screen_tile_quad(&self, letter: f32, number: f32) -> Quad {
+        let (scale_x, scale_y) = self.board_scale();
+        let (center_x, center_y) = self.board_center();
+        return Quad {
+            offset: [
+                center_x + (letter * TILE_SIZE - (1.0 - TILE_SIZE * 0.5)) * scale_x,
+                center_y + (number * TILE_SIZE - (1.0 - TILE_SIZE * 0.5)) * scale_y,
+            ],
+            scale: [0.125 * scale_x, 0.125 * scale_y],
+        };
+    }
+    // left, top, width and height in pixels
+    fn pixel_quad(&self, [left, top, width, height]: [f32; 4]) -> Quad {
+        let window_width = self.window_size.width.max(1) as f32;
+        let window_height = self.window_size.height.max(1) as f32;
+        return Quad {
+            offset: [
+                (left + width * 0.5) / window_width * 2.0 - 1.0,
+                1.0 - (top + height * 0.5) / window_height * 2.0,
+            ],
+            scale: [width / window_width, height / window_height],
+        };
+    }
+    // centers a tile sized quad on the cursor
+    fn cursor_quad(&self, position: &PhysicalPosition<f64>) -> Quad {
+        let (scale_x, scale_y) = self.board_scale();
+        return Quad {
+            offset: [
+                (position.x / self.window_size.width.max(1) as f64 * 2.0 - 1.0) as f32,
+                (1.0 - position.y / self.window_size.height.max(1) as f64 * 2.0) as f32,
+            ],
+            scale: [0.125 * scale_x, 0.125 * scale_y],
+        };
+    }
+    fn get_selected_tile(&self, position: &PhysicalPosition<f64>) -> Option<TilePosition> {
+        let board_size = self.board_size();
+        let (origin_x, origin_y) = self.board_origin();
+        let x = position.x - origin_x;
+        let y = position.y - origin_y;
+        if x < 0.0 || y < 0.0 || x >= board_size || y >= board_size {
+            return None;
+        }
+        return Some(
+            TilePosition {
+                letter: (x * (8.0 / board_size)).trunc() as usize,
+                number: 7 - (y * (8.0 / board_size)).trunc() as usize,
+            }
+            .oriented(self.flipped),
+        );
+    }
+}
+// the letters along the bottom and the numbers along the left of the board as it is seen
+fn draw_coordinates<S: Surface>(
+    target: &mut S,
+    text: &text::TextRenderer,
+    board_layout: &BoardLayout,
+) {
+    let board_size = board_layout.board_size() as f32;
+    let (origin_x, origin_y) = board_layout.board_origin();
+    let (origin_x, origin_y) = (origin_x as f32, origin_y as f32);
+    let tile_size = board_size / 8.0;
+    let scale = (tile_size / 40.0).floor().max(1.0);
+    let padding = scale * 2.0;
+    for index in 0..8 {
+        // the tile in the bottom row and the one in the left column
+        let bottom = TilePosition {
+            letter: index,
+            number: 0,
+        }
+        .oriented(board_layout.flipped);
+        let left = TilePosition {
+            letter: 0,
+            number: index,
+        }
+        .oriented(board_layout.flipped);
+        let letter = (b'a' + bottom.letter as u8) as char;
+        text.draw_text(
+            target,
+            board_layout.window_size,
+            &letter.to_string(),
+            [
+                origin_x + (index + 1) as f32 * tile_size - text::GLYPH_WIDTH * scale - padding,
+                origin_y + board_size - text::GLYPH_HEIGHT * scale - padding * 0.5,
+            ],
+            scale,
+            coordinate_color(bottom),
+        );
+        text.draw_text(
+            target,
+            board_layout.window_size,
+            &(left.number + 1).to_string(),
+            [
+                origin_x + padding,
+                origin_y + (7 - index) as f32 * tile_size + padding,
+            ],
+            scale,
+            coordinate_color(left),
+        );
+    }
+}
+fn coordinate_color(tile: TilePosition) -> [f32; 4] {
+    return pictures::coordinate_color(tile).map(|channel| channel as f32 / 255.0);
+}
+// released on the tile it started on it is a circle
+fn annotation(origin: TilePosition, target: TilePosition, color: AnnotationColor) -> Annotation {
+    if origin == target {
+        return Annotation::Circle {
+            tile: origin,
+            color,
+        };
+    }
+    return Annotation::Arrow {
+        origin,
+        target,
+        color,
+    };
+}
+// green without a modifier, red with shift, yellow with control and blue with alt
+fn annotation_color(modifiers: glutin::event::ModifiersState) -> AnnotationColor {
+    if modifiers.shift() {
+        return AnnotationColor::Red;
+    }
+    if modifiers.ctrl() {
+        return AnnotationColor::Yellow;
+    }
+    if modifiers.alt() {
+        return AnnotationColor::Blue;
+    }
+    return AnnotationColor::Green;
+}
+// the team to move gets the clock, nobody does once the play ended the game
+fn press_clock(clock: Option<&mut ChessClock>, game: &Game, now: Instant) {
+    let Some(clock) = clock else {
+        return;
+    };
+    match GameResult::of(game.game_state()) {
+        GameResult::Ongoing => clock.press(game.game_state().turn, now),
+        _ => {
+            clock.pause(now);
+        }
+    }
+}
+
+// the clock runs again if the editor stopped it
+fn resume_game(game: &Game, clock: Option<&mut ChessClock>, paused_clock: bool, now: Instant) {
+    if let (Some(clock), true) = (clock, paused_clock) {
+        clock.resume(game.game_state().turn, now);
+    }
+}
+fn start_engine(
+    game_state: GameState,
+    limits: SearchLimits,
+    event_loop_proxy: glutin::event_loop::EventLoopProxy<EngineResult>,
+) {
+    std::thread::spawn(move || {
+        let result = solver::search(&game_state, limits);
+        // fails only when the window is already closed
+        let _ = event_loop_proxy.send_event(EngineResult { game_state, result });
+    });
+}
+// a promotion opens the picker instead of playing right away
+fn try_play(
+    origin: TilePosition,
+    target: TilePosition,
+    game: &mut Game,
+    promotion_choices: &mut Vec<Play>,
+) -> bool {
+    let plays: Vec<Play> = Play::get_possible_plays_for_tile(origin, game.game_state())
+        .into_iter()
+        .filter(|play| play.target == target)
+        .collect();
+    match plays.len() {
+        0 => return false,
+        1 => {
+            game.play(plays[0]);
+            return true;
+        }
+        _ => {
+            *promotion_choices = plays;
+            return false;
+        }
+    }
+}
+// the picker covers the promotion tile and the ones below it, towards the middle
+fn promotion_tiles(promotion_choices: &[Play]) -> Vec<TilePosition> {
+    let Some(first) = promotion_choices.first() else {
+        return vec![];
+    };
+    return (0..promotion_choices.len())
+        .map(|index| TilePosition {
+            letter: first.target.letter,
+            number: match first.target.number {
+                7 => 7 - index,
+                number => number + index,
+            },
+        })
+        .collect();
+}
+// against the solver, steps back to the last position the human has to play in
+fn undo(game: &mut Game, engine: Option<Team>) -> bool {
+    if !game.undo() {
+        return false;
+    }
+    while engine == Some(game.game_state().turn) && game.undo() {}
+    return true;
+}
+fn redo(game: &mut Game, engine: Option<Team>) -> bool {
+    if !game.redo() {
+        return false;
+    }
+    while engine == Some(game.game_state().turn) && game.redo() {}
+    return true;
+}
@@ -0,0 +1,73 @@
+use deep_red::chess::*;
+use deep_red::game::PlayRecord;
+use std::time::Duration;
+
+pub struct Animation {
+    // origin and target of the moving pieces, the king and the rook when castling
+    moves: Vec<(TilePosition, TilePosition)>,
+    // fades out where it was taken
+    captured: Option<(TilePosition, PieceWithTeam)>,
+    elapsed: Duration,
+    duration: Duration,
+}
+
+impl Animation {
+    pub fn new(record: &PlayRecord, duration: Duration) -> Option<Self> {
+        let play = record.play;
+        if duration.is_zero() || matches!(play.kind, PlayKind::Drop(_)) {
+            return None;
+        }
+        let mut moves = vec![(play.origin, play.target)];
+        if let PlayKind::Rochade(side) = play.kind {
+            let (rook_letter, rook_target_letter) = match side {
+                RochadeSide::KingSide => (7, 5),
+                RochadeSide::QueenSide => (0, 3),
+            };
+            moves.push((
+                TilePosition {
+                    letter: rook_letter,
+                    number: play.origin.number,
+                },
+                TilePosition {
+                    letter: rook_target_letter,
+                    number: play.origin.number,
+                },
+            ));
+        }
+        let captured_tile = match play.kind {
+            // the taken pawn is next to the origin, not on the target
+            PlayKind::EnPassante => TilePosition {
+                letter: play.target.letter,
+                number: play.origin.number,
+            },
+            _ => play.target,
+        };
+        return Some(Self {
+            moves,
+            captured: play.captured.map(|captured| (captured_tile, captured)),
+            elapsed: Duration::ZERO,
+            duration,
+        });
+    }
+    // false once the animation is over
+    pub fn advance(&mut self, delta_time: Duration) -> bool {
+        self.elapsed += delta_time;
+        return self.elapsed < self.duration;
+    }
+    // from 0 to 1, slow at the start and the end
+    pub fn progress(&self) -> f32 {
+        let linear = (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0);
+        return linear * linear * (3.0 - 2.0 * linear);
+    }
+    // where the piece now standing on `tile` comes from
+    pub fn origin_of(&self, tile: TilePosition) -> Option<TilePosition> {
+        return self
+            .moves
+            .iter()
+            .find(|(_, target)| *target == tile)
+            .map(|(origin, _)| *origin);
+    }
+    pub fn captured(&self) -> Option<(TilePosition, PieceWithTeam)> {
+        return self.captured;
+    }
+}
@@ -0,0 +1,123 @@
+use super::text::to_linear;
+use super::BoardLayout;
+use deep_red::game::{Annotation, AnnotationColor};
+use deep_red::pictures::{arrow_outline, CIRCLE_RADIUS, CIRCLE_WIDTH};
+use glium::{implement_vertex, Surface};
+use std::f32::consts::TAU;
+
+#[derive(Clone, Copy)]
+struct ShapeVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+implement_vertex!(ShapeVertex, position, color);
+
+const CIRCLE_SEGMENTS: usize = 48;
+
+// arrows and circles as plain colored triangles
+pub struct AnnotationRenderer {
+    program: glium::Program,
+}
+
+impl AnnotationRenderer {
+    pub fn new(display: &glium::Display) -> Self {
+        let program = glium::Program::from_source(
+            display,
+            include_str!("../shape_vertex_shader.glsl"),
+            include_str!("../shape_fragment_shader.glsl"),
+            None,
+        )
+        .unwrap();
+        return Self { program };
+    }
+    pub fn draw<S: Surface>(
+        &self,
+        target: &mut S,
+        display: &glium::Display,
+        board_layout: &BoardLayout,
+        annotations: &[Annotation],
+    ) {
+        let tile_size = board_layout.board_size() as f32 / 8.0;
+        // triangles in pixels, the y axis going down
+        let mut triangles: Vec<([f32; 2], [f32; 4])> = vec![];
+        for annotation in annotations {
+            let color = to_linear(color_of(annotation.color()));
+            match *annotation {
+                Annotation::Arrow { origin, target, .. } => {
+                    let outline = arrow_outline(
+                        board_layout.tile_center(origin),
+                        board_layout.tile_center(target),
+                        tile_size,
+                    );
+                    // the shaft as two triangles and the head as one
+                    for index in [0, 6, 1, 1, 6, 5, 2, 4, 3] {
+                        triangles.push((outline[index], color));
+                    }
+                }
+                Annotation::Circle { tile, .. } => {
+                    let center = board_layout.tile_center(tile);
+                    let outer = CIRCLE_RADIUS * tile_size;
+                    let inner = (CIRCLE_RADIUS - CIRCLE_WIDTH) * tile_size;
+                    let point = |radius: f32, segment: usize| {
+                        let angle = segment as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+                        [
+                            center[0] + angle.cos() * radius,
+                            center[1] + angle.sin() * radius,
+                        ]
+                    };
+                    for segment in 0..CIRCLE_SEGMENTS {
+                        for corner in [
+                            point(outer, segment),
+                            point(inner, segment),
+                            point(outer, segment + 1),
+                            point(outer, segment + 1),
+                            point(inner, segment),
+                            point(inner, segment + 1),
+                        ] {
+                            triangles.push((corner, color));
+                        }
+                    }
+                }
+            }
+        }
+        if triangles.is_empty() {
+            return;
+        }
+
+        let window_width = board_layout.window_size.width.max(1) as f32;
+        let window_height = board_layout.window_size.height.max(1) as f32;
+        let vertices: Vec<ShapeVertex> = triangles
+            .into_iter()
+            .map(|(position, color)| ShapeVertex {
+                position: [
+                    position[0] / window_width * 2.0 - 1.0,
+                    1.0 - position[1] / window_height * 2.0,
+                ],
+                color,
+            })
+            .collect();
+        let vertex_buffer = glium::VertexBuffer::new(display, &vertices).unwrap();
+        let draw_parameters = glium::DrawParameters {
+            blend: glium::draw_parameters::Blend::alpha_blending(),
+            ..glium::draw_parameters::DrawParameters::default()
+        };
+        target
+            .draw(
+                &vertex_buffer,
+                glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+                &self.program,
+                &glium::uniforms::EmptyUniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+    }
+}
+
+fn color_of(color: AnnotationColor) -> [f32; 4] {
+    match color {
+        AnnotationColor::Green => return [0.08, 0.47, 0.12, 0.8],
+        AnnotationColor::Red => return [0.53, 0.0, 0.0, 0.8],
+        AnnotationColor::Yellow => return [0.9, 0.63, 0.0, 0.8],
+        AnnotationColor::Blue => return [0.0, 0.19, 0.53, 0.8],
+    }
+}
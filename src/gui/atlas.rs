@@ -0,0 +1,85 @@
+use super::PieceOrBoard;
+use std::collections::HashMap;
+
+const ATLAS_WIDTH: u32 = 1024;
+// transparent pixels between the sprites, so they do not bleed into each other
+const PADDING: u32 = 2;
+
+// every sprite packed into one texture, so everything is drawn with the same one
+pub struct Atlas {
+    pub texture: glium::texture::SrgbTexture2d,
+    // left, bottom, width and height in texture coordinates
+    rects: HashMap<PieceOrBoard, [f32; 4]>,
+}
+
+impl Atlas {
+    // each picture with the sprites drawn with it
+    pub fn new(
+        display: &glium::Display,
+        pictures: &[(Vec<PieceOrBoard>, image::RgbaImage)],
+    ) -> Self {
+        let images: Vec<&image::RgbaImage> = pictures.iter().map(|(_, image)| image).collect();
+
+        // shelves from left to right, the highest images first
+        let mut order: Vec<usize> = (0..images.len()).collect();
+        order.sort_by_key(|index| std::cmp::Reverse(images[*index].height()));
+        let mut positions = vec![(0, 0); images.len()];
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+        for index in order {
+            let (width, height) = images[index].dimensions();
+            if x > 0 && x + width > ATLAS_WIDTH {
+                x = 0;
+                y += shelf_height + PADDING;
+                shelf_height = 0;
+            }
+            positions[index] = (x, y);
+            x += width + PADDING;
+            shelf_height = shelf_height.max(height);
+        }
+        let atlas_width = images
+            .iter()
+            .map(|image| image.width())
+            .max()
+            .unwrap_or(1)
+            .max(ATLAS_WIDTH);
+        let atlas_height = (y + shelf_height).max(1);
+
+        let mut atlas = image::RgbaImage::new(atlas_width, atlas_height);
+        let mut file_rects = vec![];
+        for (image, (x, y)) in images.iter().zip(positions) {
+            image::imageops::replace(&mut atlas, *image, x as i64, y as i64);
+            // the texture is uploaded upside down, the bottom row comes first
+            file_rects.push([
+                x as f32 / atlas_width as f32,
+                1.0 - (y + image.height()) as f32 / atlas_height as f32,
+                image.width() as f32 / atlas_width as f32,
+                image.height() as f32 / atlas_height as f32,
+            ]);
+        }
+        let mut rects = HashMap::new();
+        for ((sprites, _), rect) in pictures.iter().zip(file_rects) {
+            for sprite in sprites {
+                rects.insert(*sprite, rect);
+            }
+        }
+
+        let image = glium::texture::RawImage2d::from_raw_rgba_reversed(
+            &atlas.into_raw(),
+            (atlas_width, atlas_height),
+        );
+        // smaller mipmap levels would mix neighbouring sprites
+        let texture = glium::texture::SrgbTexture2d::with_mipmaps(
+            display,
+            image,
+            glium::texture::MipmapsOption::NoMipmap,
+        )
+        .unwrap();
+        return Self { texture, rects };
+    }
+    pub fn rect(&self, key: PieceOrBoard) -> [f32; 4] {
+        let Some(rect) = self.rects.get(&key) else {
+            panic!("texture does not exist");
+        };
+        return *rect;
+    }
+}
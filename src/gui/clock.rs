@@ -0,0 +1,90 @@
+use deep_red::chess::Team;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    pub time: Duration,
+    // added after every play of the team
+    pub increment: Duration,
+}
+
+pub struct ChessClock {
+    // white first
+    remaining: [Duration; 2],
+    increment: Duration,
+    // nothing runs before the first play and after a flag fell
+    running: Option<(Team, Instant)>,
+}
+
+impl ChessClock {
+    pub fn new(time_control: TimeControl) -> Self {
+        return Self {
+            remaining: [time_control.time; 2],
+            increment: time_control.increment,
+            running: None,
+        };
+    }
+    pub fn time_left(&self, team: Team, now: Instant) -> Duration {
+        let remaining = self.remaining[index(team)];
+        match self.running {
+            Some((running_team, since)) if running_team == team => {
+                return remaining.saturating_sub(now.saturating_duration_since(since));
+            }
+            _ => return remaining,
+        }
+    }
+    pub fn running(&self) -> Option<Team> {
+        return self.running.map(|(team, _)| team);
+    }
+    // the team that just played gets its increment, `turn` has to play next
+    pub fn press(&mut self, turn: Team, now: Instant) {
+        if let Some(team) = self.stop(now) {
+            self.remaining[index(team)] += self.increment;
+        }
+        self.running = Some((turn, now));
+    }
+    // undoing and redoing plays hands the clock over without an increment
+    pub fn hand_over(&mut self, turn: Team, now: Instant) {
+        if self.stop(now).is_some() {
+            self.running = Some((turn, now));
+        }
+    }
+    // while the board editor is open, true if the clock was running
+    pub fn pause(&mut self, now: Instant) -> bool {
+        return self.stop(now).is_some();
+    }
+    pub fn resume(&mut self, turn: Team, now: Instant) {
+        self.running = Some((turn, now));
+    }
+    // the team whose time ran out, the clock stops then
+    pub fn flag(&mut self, now: Instant) -> Option<Team> {
+        let team = self.running()?;
+        if self.time_left(team, now) > Duration::ZERO {
+            return None;
+        }
+        self.stop(now);
+        return Some(team);
+    }
+    fn stop(&mut self, now: Instant) -> Option<Team> {
+        let (team, _) = self.running?;
+        self.remaining[index(team)] = self.time_left(team, now);
+        self.running = None;
+        return Some(team);
+    }
+}
+
+fn index(team: Team) -> usize {
+    match team {
+        Team::White => return 0,
+        Team::Black => return 1,
+    }
+}
+
+// minutes and seconds, tenths too once it gets close
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds < 10 {
+        return format!("0:{:02}.{}", seconds, time.subsec_millis() / 100);
+    }
+    return format!("{}:{:02}", seconds / 60, seconds % 60);
+}
@@ -0,0 +1,313 @@
+use super::sidebar::{text_metrics, BACKGROUND, CURRENT, TEXT};
+use super::text::{TextRenderer, GLYPH_WIDTH};
+use super::{BoardLayout, PieceOrBoard, Quad};
+use deep_red::chess::*;
+use deep_red::pictures::all_pieces;
+use glium::glutin::dpi::PhysicalPosition;
+use glium::Surface;
+
+// the palette cells are this many tiles wide
+const PALETTE_TILES: f32 = 0.75;
+const PALETTE_ROWS: usize = 6;
+
+// setting up a position on its own board, the game only changes once it is started
+pub struct Editor {
+    pub game_state: GameState,
+    // placed by clicking on the board, without one clicking removes pieces
+    pub brush: Option<PieceWithTeam>,
+    // why the last click did nothing
+    pub problem: Option<String>,
+    // the clock of the game was running before the editor opened
+    pub paused_clock: bool,
+}
+
+pub enum EditorAction {
+    Start(GameState),
+    Cancel,
+}
+
+#[derive(Clone, Copy)]
+enum Control {
+    Turn,
+    Rochade(Team, RochadeSide),
+    Clear,
+    StartingPosition,
+    Start,
+    Cancel,
+}
+
+const CONTROLS: [Control; 9] = [
+    Control::Turn,
+    Control::Rochade(Team::White, RochadeSide::KingSide),
+    Control::Rochade(Team::White, RochadeSide::QueenSide),
+    Control::Rochade(Team::Black, RochadeSide::KingSide),
+    Control::Rochade(Team::Black, RochadeSide::QueenSide),
+    Control::Clear,
+    Control::StartingPosition,
+    Control::Start,
+    Control::Cancel,
+];
+
+impl Editor {
+    pub fn new(game_state: GameState, paused_clock: bool) -> Self {
+        return Self {
+            game_state,
+            brush: None,
+            problem: None,
+            paused_clock,
+        };
+    }
+    pub fn click(
+        &mut self,
+        board_layout: &BoardLayout,
+        position: &PhysicalPosition<f64>,
+    ) -> Option<EditorAction> {
+        self.problem = None;
+        if let Some(tile) = board_layout.get_selected_tile(position) {
+            self.place(tile);
+            return None;
+        }
+        let [x, y] = [position.x as f32, position.y as f32];
+        let inside = |[left, top, width, height]: [f32; 4]| {
+            x >= left && y >= top && x < left + width && y < top + height
+        };
+        for (index, piece_with_team) in all_pieces().into_iter().enumerate() {
+            if inside(palette_rect(board_layout, index)) {
+                // clicking the chosen piece again picks nothing, which removes pieces
+                self.brush = match self.brush == Some(piece_with_team) {
+                    true => None,
+                    false => Some(piece_with_team),
+                };
+                return None;
+            }
+        }
+        let control = (0..CONTROLS.len()).find(|index| inside(control_rect(board_layout, *index)));
+        match control.map(|index| CONTROLS[index]) {
+            Some(Control::Turn) => self.game_state.turn = !self.game_state.turn,
+            Some(Control::Rochade(team, side)) => self.toggle_rochade(team, side),
+            Some(Control::Clear) => self.game_state = GameState::empty(),
+            Some(Control::StartingPosition) => self.game_state = GameState::new(),
+            Some(Control::Start) => {
+                let mut game_state = self.game_state;
+                remove_stale_rochade(&mut game_state);
+                match game_state.validate().first() {
+                    Some(problem) => self.problem = Some(problem.to_string()),
+                    None => return Some(EditorAction::Start(game_state)),
+                }
+            }
+            Some(Control::Cancel) => return Some(EditorAction::Cancel),
+            None => {}
+        }
+        return None;
+    }
+    // the same piece twice takes it away again
+    fn place(&mut self, tile: TilePosition) {
+        let occupant = &mut self.game_state.board[tile.number][tile.letter];
+        let same = match (*occupant, self.brush) {
+            (Some(placed), Some(brush)) => placed.to_char() == brush.to_char(),
+            _ => false,
+        };
+        *occupant = match same {
+            true => None,
+            false => self.brush,
+        };
+    }
+    fn toggle_rochade(&mut self, team: Team, side: RochadeSide) {
+        let (number, rook_letter) = rochade_tiles(team, side);
+        let able = has_rochade(&self.game_state, team, side);
+        let board = &mut self.game_state.board;
+        if able {
+            board[number][rook_letter] = Some(PieceWithTeam {
+                piece: Piece::Rook(RochadeAbility::Unable),
+                team,
+            });
+            return;
+        }
+        let king = board[number][4]
+            .filter(|king| king.team == team && matches!(king.piece, Piece::King(_)));
+        let rook = board[number][rook_letter]
+            .filter(|rook| rook.team == team && matches!(rook.piece, Piece::Rook(_)));
+        if king.is_none() || rook.is_none() {
+            self.problem = Some(format!(
+                "needs K{} and R{}",
+                TilePosition { letter: 4, number },
+                TilePosition {
+                    letter: rook_letter,
+                    number
+                },
+            ));
+            return;
+        }
+        board[number][4] = Some(PieceWithTeam {
+            piece: Piece::King(RochadeAbility::Able),
+            team,
+        });
+        board[number][rook_letter] = Some(PieceWithTeam {
+            piece: Piece::Rook(RochadeAbility::Able),
+            team,
+        });
+    }
+    // the palette, drawn with the other sprites
+    pub(super) fn sprites(&self, board_layout: &BoardLayout) -> Vec<(PieceOrBoard, Quad)> {
+        let mut sprites = vec![];
+        for (index, piece_with_team) in all_pieces().into_iter().enumerate() {
+            let quad = board_layout.pixel_quad(palette_rect(board_layout, index));
+            if self.brush == Some(piece_with_team) {
+                sprites.push((PieceOrBoard::Blue, quad));
+            }
+            sprites.push((PieceOrBoard::Piece(piece_with_team), quad));
+        }
+        return sprites;
+    }
+    // the background and the controls below the palette, before the sprites are drawn
+    pub fn draw_panel<S: Surface>(
+        &self,
+        target: &mut S,
+        text: &TextRenderer,
+        board_layout: &BoardLayout,
+    ) {
+        let window_size = board_layout.window_size;
+        text.draw_rect(target, window_size, board_layout.sidebar_rect(), BACKGROUND);
+        let (scale, line_height) = text_metrics(board_layout);
+        for (index, control) in CONTROLS.into_iter().enumerate() {
+            let line = match control {
+                Control::Turn => match self.game_state.turn {
+                    Team::White => "white to move".to_string(),
+                    Team::Black => "black to move".to_string(),
+                },
+                Control::Rochade(team, side) => format!(
+                    "[{}] {} {}",
+                    match has_rochade(&self.game_state, team, side) {
+                        true => 'x',
+                        false => ' ',
+                    },
+                    match team {
+                        Team::White => "white",
+                        Team::Black => "black",
+                    },
+                    match side {
+                        RochadeSide::KingSide => "O-O",
+                        RochadeSide::QueenSide => "O-O-O",
+                    }
+                ),
+                Control::Clear => "clear the board".to_string(),
+                Control::StartingPosition => "starting position".to_string(),
+                Control::Start => "start".to_string(),
+                Control::Cancel => "cancel".to_string(),
+            };
+            let [left, top, _, _] = control_rect(board_layout, index);
+            text.draw_text(
+                target,
+                window_size,
+                &line,
+                [left + GLYPH_WIDTH * scale, top],
+                scale,
+                TEXT,
+            );
+        }
+        if let Some(problem) = &self.problem {
+            let [left, top, width, _] = control_rect(board_layout, CONTROLS.len());
+            // wrapped at the words, with a character of space on both sides
+            let columns = ((width / (GLYPH_WIDTH * scale)) as usize).saturating_sub(2);
+            let mut lines: Vec<String> = vec![];
+            for word in problem.split(' ') {
+                match lines.last_mut() {
+                    Some(line) if line.len() + 1 + word.len() <= columns => {
+                        line.push(' ');
+                        line.push_str(word);
+                    }
+                    _ => lines.push(word.to_string()),
+                }
+            }
+            for (index, line) in lines.iter().enumerate() {
+                text.draw_text(
+                    target,
+                    window_size,
+                    line,
+                    [
+                        left + GLYPH_WIDTH * scale,
+                        top + line_height * (index as f32 + 0.5),
+                    ],
+                    scale,
+                    CURRENT,
+                );
+            }
+        }
+    }
+}
+
+// left, top, width and height in pixels, two columns of six centered in the sidebar
+fn palette_rect(board_layout: &BoardLayout, index: usize) -> [f32; 4] {
+    let [left, top, width, height] = board_layout.sidebar_rect();
+    let tile_size = height / 8.0;
+    let cell = tile_size * PALETTE_TILES;
+    return [
+        left + (width - cell * 2.0) * 0.5 + (index / PALETTE_ROWS) as f32 * cell,
+        top + tile_size * 0.25 + (index % PALETTE_ROWS) as f32 * cell,
+        cell,
+        cell,
+    ];
+}
+// one line of the sidebar wide, below the palette
+fn control_rect(board_layout: &BoardLayout, index: usize) -> [f32; 4] {
+    let [left, top, width, height] = board_layout.sidebar_rect();
+    let tile_size = height / 8.0;
+    let (_, line_height) = text_metrics(board_layout);
+    let palette_bottom = top + tile_size * (0.25 + PALETTE_ROWS as f32 * PALETTE_TILES);
+    return [
+        left,
+        palette_bottom + tile_size * 0.25 + index as f32 * line_height,
+        width,
+        line_height,
+    ];
+}
+// the row of the king and the letter of the rook
+fn rochade_tiles(team: Team, side: RochadeSide) -> (usize, usize) {
+    let number = match team {
+        Team::White => 0,
+        Team::Black => 7,
+    };
+    match side {
+        RochadeSide::KingSide => return (number, 7),
+        RochadeSide::QueenSide => return (number, 0),
+    }
+}
+// the king and the rook have both not moved, like a rochade needs it
+fn has_rochade(game_state: &GameState, team: Team, side: RochadeSide) -> bool {
+    let (number, rook_letter) = rochade_tiles(team, side);
+    return game_state.board[number][4]
+        == Some(PieceWithTeam {
+            piece: Piece::King(RochadeAbility::Able),
+            team,
+        })
+        && game_state.board[number][rook_letter]
+            == Some(PieceWithTeam {
+                piece: Piece::Rook(RochadeAbility::Able),
+                team,
+            });
+}
+// a rook that lost its king or its king's other rook can not do a rochade anymore,
+// and a king without rooks neither
+fn remove_stale_rochade(game_state: &mut GameState) {
+    for team in [Team::White, Team::Black] {
+        let mut any = false;
+        for side in [RochadeSide::KingSide, RochadeSide::QueenSide] {
+            let (number, rook_letter) = rochade_tiles(team, side);
+            if has_rochade(game_state, team, side) {
+                any = true;
+                continue;
+            }
+            if let Some(rook) = &mut game_state.board[number][rook_letter] {
+                if rook.team == team && rook.piece == Piece::Rook(RochadeAbility::Able) {
+                    rook.piece = Piece::Rook(RochadeAbility::Unable);
+                }
+            }
+        }
+        let (number, _) = rochade_tiles(team, RochadeSide::KingSide);
+        if let Some(king) = &mut game_state.board[number][4] {
+            if !any && king.team == team && king.piece == Piece::King(RochadeAbility::Able) {
+                king.piece = Piece::King(RochadeAbility::Unable);
+            }
+        }
+    }
+}
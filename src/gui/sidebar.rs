@@ -0,0 +1,142 @@
+use super::clock::{format_time, ChessClock};
+use super::text::{TextRenderer, GLYPH_HEIGHT, GLYPH_WIDTH};
+use super::BoardLayout;
+use deep_red::chess::*;
+use deep_red::game::{Game, GameResult};
+use deep_red::solver::SearchResult;
+use deep_red::terminal::format_evaluation;
+use glium::Surface;
+use std::time::Instant;
+
+pub(super) const BACKGROUND: [f32; 4] = [0.16, 0.16, 0.16, 1.0];
+pub(super) const TEXT: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+// undone plays and the clock that is not running
+pub(super) const DIMMED: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+pub(super) const CURRENT: [f32; 4] = [0.95, 0.35, 0.3, 1.0];
+
+// everything the sidebar shows besides the game itself
+pub struct SidebarState<'a> {
+    pub clock: Option<&'a ChessClock>,
+    pub flagged: Option<Team>,
+    pub engine: Option<Team>,
+    // the last search of the solver and the team it searched for
+    pub evaluation: Option<(SearchResult, Team)>,
+}
+
+// the font scale and the line height of the sidebar
+pub(super) fn text_metrics(board_layout: &BoardLayout) -> (f32, f32) {
+    let [_, _, _, height] = board_layout.sidebar_rect();
+    // whole font pixels keep the glyphs sharp
+    let scale = (height / 360.0).floor().max(1.0);
+    return (scale, GLYPH_HEIGHT * scale * 1.5);
+}
+
+pub fn draw_sidebar<S: Surface>(
+    target: &mut S,
+    text: &TextRenderer,
+    board_layout: &BoardLayout,
+    game: &Game,
+    state: SidebarState,
+    now: Instant,
+) {
+    let window_size = board_layout.window_size;
+    let [left, top, width, height] = board_layout.sidebar_rect();
+    text.draw_rect(target, window_size, [left, top, width, height], BACKGROUND);
+
+    let (scale, line_height) = text_metrics(board_layout);
+    let character_width = GLYPH_WIDTH * scale;
+    let left = left + character_width;
+    let mut y = top + line_height * 0.5;
+    let mut write = |line: &str, color: [f32; 4], y: &mut f32| {
+        text.draw_text(target, window_size, line, [left, *y], scale, color);
+        *y += line_height;
+    };
+
+    if let Some(clock) = state.clock {
+        for (team, name) in [(Team::White, "white"), (Team::Black, "black")] {
+            let color = match clock.running() == Some(team) {
+                true => TEXT,
+                false => DIMMED,
+            };
+            let line = format!("{}  {}", name, format_time(clock.time_left(team, now)));
+            write(&line, color, &mut y);
+        }
+        y += line_height * 0.5;
+    }
+
+    let game_state = game.game_state();
+    // the board decides before the clock, a flag can only fall while the game goes on
+    let status = match (state.flagged, GameResult::of(game_state)) {
+        (_, GameResult::WhiteWins) => "checkmate, white wins".to_string(),
+        (_, GameResult::BlackWins) => "checkmate, black wins".to_string(),
+        (_, GameResult::Draw) => "stalemate".to_string(),
+        (Some(Team::White), GameResult::Ongoing) => "black wins on time".to_string(),
+        (Some(Team::Black), GameResult::Ongoing) => "white wins on time".to_string(),
+        (None, GameResult::Ongoing) => {
+            let team = match game_state.turn {
+                Team::White => "white",
+                Team::Black => "black",
+            };
+            match state.engine == Some(game_state.turn) {
+                true => format!("{} is thinking", team),
+                false => format!("{} to move", team),
+            }
+        }
+    };
+    write(&status, TEXT, &mut y);
+
+    if let Some((result, team)) = state.evaluation {
+        // from the view of the team the solver played for, which was to move when it searched,
+        // like the terminal and the commands
+        let team = match team {
+            Team::White => "white",
+            Team::Black => "black",
+        };
+        let line = format!(
+            "eval {} {} depth {}",
+            team,
+            format_evaluation(result.evaluation),
+            result.depth
+        );
+        write(&line, TEXT, &mut y);
+    }
+    y += line_height * 0.5;
+
+    // a game starting with black to move leaves white's first column empty
+    let offset = match game.initial_game_state().turn {
+        Team::White => 0,
+        Team::Black => 1,
+    };
+    let plays = game.plays();
+    let line_count = (plays.len() + offset).div_ceil(2);
+    let visible_lines = ((top + height - y) / line_height).max(0.0) as usize;
+    // scrolls so the current play stays visible
+    let current_line = (game.ply() + offset).saturating_sub(1) / 2;
+    let first_line = (current_line + 1).saturating_sub(visible_lines);
+    // numbered like in pgn, from the fullmove number the game started at
+    let first_number = game.initial_fullmove_number() as usize;
+    let number_width = (first_number + line_count).to_string().len().max(2) + 2;
+    for line in (first_line..line_count).take(visible_lines) {
+        let number = match (line, offset) {
+            (0, 1) => format!("{}...", first_number),
+            _ => format!("{}.", first_number + line),
+        };
+        text.draw_text(target, window_size, &number, [left, y], scale, DIMMED);
+        for column in 0..2 {
+            let Some(index) = (line * 2 + column).checked_sub(offset) else {
+                continue;
+            };
+            let Some(record) = plays.get(index) else {
+                continue;
+            };
+            let color = match index + 1 {
+                ply if ply == game.ply() => CURRENT,
+                ply if ply < game.ply() => TEXT,
+                _ => DIMMED,
+            };
+            let x = left + character_width * (number_width + column * 9) as f32;
+            text.draw_text(target, window_size, &record.san, [x, y], scale, color);
+        }
+        y += line_height;
+    }
+}
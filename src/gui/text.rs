@@ -0,0 +1,188 @@
+use super::Vertex;
+use deep_red::font::{glyph_index, FONT};
+use glium::glutin::dpi::PhysicalSize;
+use glium::{uniform, Surface};
+
+// every glyph gets a cell one pixel wider and taller than itself, so there is space between them
+pub const GLYPH_WIDTH: f32 = 6.0;
+pub const GLYPH_HEIGHT: f32 = 8.0;
+// the cell after the glyphs is filled, it is used for plain rectangles
+const FILLED_CELL: usize = 95;
+
+pub struct TextRenderer {
+    texture: glium::texture::Texture2d,
+    program: glium::Program,
+    vertex_buffer: glium::VertexBuffer<Vertex>,
+    indices: glium::IndexBuffer<u16>,
+}
+
+impl TextRenderer {
+    pub fn new(display: &glium::Display) -> Self {
+        let cells = FONT.len() + 1;
+        let width = cells * GLYPH_WIDTH as usize;
+        let height = GLYPH_HEIGHT as usize;
+        let mut pixels = vec![0u8; width * height * 4];
+        for (index, columns) in FONT.iter().enumerate() {
+            for (column, bits) in columns.iter().enumerate() {
+                for row in 0..7 {
+                    if bits & (1 << row) == 0 {
+                        continue;
+                    }
+                    let pixel = (row * width + index * GLYPH_WIDTH as usize + column) * 4;
+                    pixels[pixel..pixel + 4].copy_from_slice(&[255, 255, 255, 255]);
+                }
+            }
+        }
+        for row in 0..height {
+            for column in 0..GLYPH_WIDTH as usize {
+                let pixel = (row * width + FILLED_CELL * GLYPH_WIDTH as usize + column) * 4;
+                pixels[pixel..pixel + 4].copy_from_slice(&[255, 255, 255, 255]);
+            }
+        }
+        let image = glium::texture::RawImage2d::from_raw_rgba_reversed(
+            &pixels,
+            (width as u32, height as u32),
+        );
+        let texture = glium::texture::Texture2d::new(display, image).unwrap();
+
+        let program = glium::Program::from_source(
+            display,
+            include_str!("../text_vertex_shader.glsl"),
+            include_str!("../text_fragment_shader.glsl"),
+            None,
+        )
+        .unwrap();
+        let vertices = [
+            Vertex {
+                position: [-1.0, -1.0],
+                texture_position: [0.0, 0.0],
+            },
+            Vertex {
+                position: [1.0, -1.0],
+                texture_position: [1.0, 0.0],
+            },
+            Vertex {
+                position: [1.0, 1.0],
+                texture_position: [1.0, 1.0],
+            },
+            Vertex {
+                position: [-1.0, 1.0],
+                texture_position: [0.0, 1.0],
+            },
+        ];
+        let vertex_buffer = glium::VertexBuffer::new(display, &vertices).unwrap();
+        let indices = glium::IndexBuffer::new(
+            display,
+            glium::index::PrimitiveType::TrianglesList,
+            &[0u16, 1, 2, 2, 3, 0],
+        )
+        .unwrap();
+        return Self {
+            texture,
+            program,
+            vertex_buffer,
+            indices,
+        };
+    }
+    // the position is the top left corner in physical pixels, `scale` pixels per font pixel
+    pub fn draw_text<S: Surface>(
+        &self,
+        target: &mut S,
+        window_size: PhysicalSize<u32>,
+        text: &str,
+        position: [f32; 2],
+        scale: f32,
+        color: [f32; 4],
+    ) {
+        let [x, y] = position;
+        for (index, character) in text.chars().enumerate() {
+            let cell = glyph_index(character);
+            if cell == 0 {
+                continue;
+            }
+            self.draw_cell(
+                target,
+                window_size,
+                cell,
+                [
+                    x + index as f32 * GLYPH_WIDTH * scale,
+                    y,
+                    GLYPH_WIDTH * scale,
+                    GLYPH_HEIGHT * scale,
+                ],
+                color,
+            );
+        }
+    }
+    pub fn draw_rect<S: Surface>(
+        &self,
+        target: &mut S,
+        window_size: PhysicalSize<u32>,
+        rect: [f32; 4],
+        color: [f32; 4],
+    ) {
+        self.draw_cell(target, window_size, FILLED_CELL, rect, color);
+    }
+    fn draw_cell<S: Surface>(
+        &self,
+        target: &mut S,
+        window_size: PhysicalSize<u32>,
+        cell: usize,
+        rect: [f32; 4],
+        color: [f32; 4],
+    ) {
+        let [x, y, width, height] = rect;
+        let window_width = window_size.width.max(1) as f32;
+        let window_height = window_size.height.max(1) as f32;
+        let matrix = [
+            [width / window_width, 0.0, 0.0, 0.0],
+            [0.0, height / window_height, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [
+                (x + width * 0.5) / window_width * 2.0 - 1.0,
+                1.0 - (y + height * 0.5) / window_height * 2.0,
+                0.0,
+                1.0f32,
+            ],
+        ];
+        let cells = (FONT.len() + 1) as f32;
+        let uniforms = uniform! {
+            matrix: matrix,
+            texture_rect: [cell as f32 / cells, 0.0, 1.0 / cells, 1.0f32],
+            tint: to_linear(color),
+            tex: self.texture
+                .sampled()
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest),
+        };
+        let draw_parameters = glium::DrawParameters {
+            blend: glium::draw_parameters::Blend::alpha_blending(),
+            ..glium::draw_parameters::DrawParameters::default()
+        };
+        target
+            .draw(
+                &self.vertex_buffer,
+                &self.indices,
+                &self.program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+    }
+}
+
+// colors are given like in image editors, the framebuffer expects them linear
+pub fn to_linear(color: [f32; 4]) -> [f32; 4] {
+    let channel = |value: f32| -> f32 {
+        if value <= 0.04045 {
+            return value / 12.92;
+        }
+        return ((value + 0.055) / 1.055).powf(2.4);
+    };
+    return [
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        color[3],
+    ];
+}
@@ -0,0 +1,194 @@
+use super::PieceOrBoard;
+use deep_red::chess::*;
+use deep_red::pictures::{all_pieces, decode_png, piece_picture, BOARD_PICTURE};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardColors {
+    pub light: [u8; 3],
+    pub dark: [u8; 3],
+}
+
+// the built in boards besides the embedded picture
+pub const BOARD_COLORS: [(&str, BoardColors); 4] = [
+    (
+        "brown",
+        BoardColors {
+            light: [240, 217, 181],
+            dark: [181, 136, 99],
+        },
+    ),
+    (
+        "green",
+        BoardColors {
+            light: [238, 238, 210],
+            dark: [118, 150, 86],
+        },
+    ),
+    (
+        "blue",
+        BoardColors {
+            light: [222, 227, 230],
+            dark: [140, 162, 173],
+        },
+    ),
+    (
+        "grey",
+        BoardColors {
+            light: [200, 200, 200],
+            dark: [120, 120, 120],
+        },
+    ),
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Theme {
+    // files named like the embedded ones replace them, the others stay embedded
+    pub directory: Option<PathBuf>,
+    // used instead of the embedded board picture, a board in the directory still wins
+    pub board_colors: Option<BoardColors>,
+}
+
+// pictures are this many pixels wide when they are made from svg or colors
+#[cfg(feature = "svg-themes")]
+const PIECE_SIZE: u32 = 256;
+const BOARD_SIZE: u32 = 1024;
+
+impl BoardColors {
+    pub fn from_name(name: &str) -> Option<Self> {
+        return BOARD_COLORS
+            .iter()
+            .find(|(other, _)| *other == name)
+            .map(|(_, colors)| *colors);
+    }
+    fn to_image(self) -> image::RgbaImage {
+        let tile_size = BOARD_SIZE / 8;
+        return image::RgbaImage::from_fn(BOARD_SIZE, BOARD_SIZE, |x, y| {
+            let letter = x / tile_size;
+            // the picture starts with the 8th row at the top
+            let number = 7 - y / tile_size;
+            let [red, green, blue] = match (letter + number) % 2 {
+                0 => self.dark,
+                _ => self.light,
+            };
+            image::Rgba([red, green, blue, 255])
+        });
+    }
+}
+
+impl Theme {
+    // every picture with the sprites drawn with it
+    pub(super) fn load(&self) -> Vec<(Vec<PieceOrBoard>, image::RgbaImage)> {
+        return artwork()
+            .into_iter()
+            .map(|(file_name, embedded, sprites)| {
+                let from_directory = self
+                    .directory
+                    .as_ref()
+                    .and_then(|directory| load_file(directory, file_name));
+                let image = match (from_directory, self.board_colors) {
+                    (Some(image), _) => image,
+                    (None, Some(colors)) if sprites == [PieceOrBoard::Board] => colors.to_image(),
+                    (None, _) => decode_png(embedded).unwrap(),
+                };
+                (sprites, image)
+            })
+            .collect();
+    }
+}
+
+// the png, or with the svg feature an svg of the same name
+fn load_file(directory: &Path, file_name: &str) -> Option<image::RgbaImage> {
+    let path = directory.join(file_name);
+    if let Ok(bytes) = std::fs::read(&path) {
+        match decode_png(&bytes) {
+            Some(image) => return Some(image),
+            None => eprintln!("{} is not a png, the embedded one is used", path.display()),
+        }
+    }
+    #[cfg(feature = "svg-themes")]
+    {
+        let path = path.with_extension("svg");
+        if let Ok(bytes) = std::fs::read(&path) {
+            let size = match file_name {
+                "schach.png" => BOARD_SIZE,
+                _ => PIECE_SIZE,
+            };
+            match rasterize_svg(&bytes, size) {
+                Some(image) => return Some(image),
+                None => eprintln!("{} is not an svg, the embedded one is used", path.display()),
+            }
+        }
+    }
+    return None;
+}
+
+#[cfg(feature = "svg-themes")]
+fn rasterize_svg(bytes: &[u8], size: u32) -> Option<image::RgbaImage> {
+    use resvg::{tiny_skia, usvg};
+
+    let tree = usvg::Tree::from_data(bytes, &usvg::Options::default()).ok()?;
+    let mut pixmap = tiny_skia::Pixmap::new(size, size)?;
+    let tree_size = tree.size();
+    let transform = tiny_skia::Transform::from_scale(
+        size as f32 / tree_size.width(),
+        size as f32 / tree_size.height(),
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    let mut image = image::RgbaImage::new(size, size);
+    for (pixel, color) in image.pixels_mut().zip(pixmap.pixels()) {
+        let color = color.demultiply();
+        *pixel = image::Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+    return Some(image);
+}
+
+// the file names of the embedded pictures and what they show
+fn artwork() -> Vec<(&'static str, &'static [u8], Vec<PieceOrBoard>)> {
+    let (board_file_name, board) = BOARD_PICTURE;
+    let mut artwork = vec![
+        (board_file_name, board, vec![PieceOrBoard::Board]),
+        (
+            "blue.png",
+            include_bytes!("../../png/blue.png").as_slice(),
+            vec![PieceOrBoard::Blue],
+        ),
+        (
+            "red.png",
+            include_bytes!("../../png/red.png").as_slice(),
+            vec![PieceOrBoard::Red],
+        ),
+    ];
+    for piece_with_team in all_pieces() {
+        let (file_name, bytes) = piece_picture(piece_with_team);
+        artwork.push((
+            file_name,
+            bytes,
+            piece_sprites(piece_with_team.piece, piece_with_team.team),
+        ));
+    }
+    return artwork;
+}
+
+// the piece looks the same whether it can still castle or be taken en passante
+fn piece_sprites(piece: Piece, team: Team) -> Vec<PieceOrBoard> {
+    let pieces = match piece {
+        Piece::King(_) => vec![
+            Piece::King(RochadeAbility::Able),
+            Piece::King(RochadeAbility::Unable),
+        ],
+        Piece::Rook(_) => vec![
+            Piece::Rook(RochadeAbility::Able),
+            Piece::Rook(RochadeAbility::Unable),
+        ],
+        Piece::Pawn(_) => vec![
+            Piece::Pawn(EnPassanteVulnerability::Vulnerable),
+            Piece::Pawn(EnPassanteVulnerability::Invulnerable),
+        ],
+        piece => vec![piece],
+    };
+    return pieces
+        .into_iter()
+        .map(|piece| PieceOrBoard::Piece(PieceWithTeam { piece, team }))
+        .collect();
+}
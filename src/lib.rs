@@ -0,0 +1,20 @@
+#![allow(clippy::needless_return)]
+
+pub mod chess;
+pub mod epd;
+pub mod font;
+pub mod game;
+pub mod notation;
+pub mod perft;
+pub mod pgn;
+pub mod pictures;
+#[cfg(feature = "render")]
+pub mod render;
+pub mod selfplay;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod solver;
+pub mod suite;
+pub mod svg;
+pub mod terminal;
+pub mod validation;
@@ -0,0 +1,614 @@
+use crate::chess::*;
+use crate::validation::PositionProblem;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotationError {
+    Fen(String),
+    Tile(String),
+    Play(String),
+    // an arrow or a circle like "Rg1f3" or "Ge4"
+    Annotation(String),
+    Pgn(String),
+    Epd(String),
+    // the fen reads fine, but the position could never come up
+    Position(Vec<PositionProblem>),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::Fen(reason) => write!(f, "invalid fen: {}", reason),
+            NotationError::Tile(tile) => write!(f, "invalid tile: {}", tile),
+            NotationError::Play(reason) => write!(f, "invalid play: {}", reason),
+            NotationError::Annotation(command) => write!(f, "invalid arrow or circle: {}", command),
+            NotationError::Pgn(reason) => write!(f, "invalid pgn: {}", reason),
+            NotationError::Epd(reason) => write!(f, "invalid epd: {}", reason),
+            NotationError::Position(problems) => {
+                let problems: Vec<String> =
+                    problems.iter().map(|problem| problem.to_string()).collect();
+                write!(f, "impossible position: {}", problems.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+impl fmt::Display for TilePosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            (b'a' + self.letter as u8) as char,
+            self.number + 1
+        )
+    }
+}
+
+impl TilePosition {
+    pub fn from_notation(notation: &str) -> Result<Self, NotationError> {
+        let bytes = notation.as_bytes();
+        if bytes.len() != 2
+            || !(b'a'..=b'h').contains(&bytes[0])
+            || !(b'1'..=b'8').contains(&bytes[1])
+        {
+            return Err(NotationError::Tile(notation.to_string()));
+        }
+        return Ok(Self {
+            letter: (bytes[0] - b'a') as usize,
+            number: (bytes[1] - b'1') as usize,
+        });
+    }
+}
+
+impl Piece {
+    // uppercase letter as used in fen and san, the pawn is 'P'
+    pub fn to_char(&self) -> char {
+        match self {
+            Piece::King(_) => 'K',
+            Piece::Queen => 'Q',
+            Piece::Rook(_) => 'R',
+            Piece::Bishop => 'B',
+            Piece::Horse => 'N',
+            Piece::Pawn(_) => 'P',
+        }
+    }
+    pub fn from_char(character: char) -> Option<Self> {
+        match character.to_ascii_uppercase() {
+            'K' => Some(Piece::King(RochadeAbility::Unable)),
+            'Q' => Some(Piece::Queen),
+            'R' => Some(Piece::Rook(RochadeAbility::Unable)),
+            'B' => Some(Piece::Bishop),
+            'N' => Some(Piece::Horse),
+            'P' => Some(Piece::Pawn(EnPassanteVulnerability::Invulnerable)),
+            _ => None,
+        }
+    }
+}
+
+impl PieceWithTeam {
+    pub fn to_char(&self) -> char {
+        match self.team {
+            Team::White => self.piece.to_char(),
+            Team::Black => self.piece.to_char().to_ascii_lowercase(),
+        }
+    }
+    pub fn from_char(character: char) -> Option<Self> {
+        let piece = Piece::from_char(character)?;
+        let team = match character.is_ascii_uppercase() {
+            true => Team::White,
+            false => Team::Black,
+        };
+        return Some(Self { piece, team });
+    }
+}
+
+impl GameState {
+    // the game state does not count plays, so the clocks are always reset
+    pub fn to_fen(&self) -> String {
+        return self.to_fen_with_counters(0, 1);
+    }
+    pub fn to_fen_with_counters(&self, halfmove_clock: u32, fullmove_number: u32) -> String {
+        let mut fen = String::new();
+        for number in (0..8).rev() {
+            let mut empty_tiles = 0;
+            for letter in 0..8 {
+                let Some(piece_with_team) = self.board[number][letter] else {
+                    empty_tiles += 1;
+                    continue;
+                };
+                if empty_tiles > 0 {
+                    fen.push_str(&empty_tiles.to_string());
+                    empty_tiles = 0;
+                }
+                fen.push(piece_with_team.to_char());
+            }
+            if empty_tiles > 0 {
+                fen.push_str(&empty_tiles.to_string());
+            }
+            if number > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(match self.turn {
+            Team::White => " w ",
+            Team::Black => " b ",
+        });
+
+        let mut rochade_rights = String::new();
+        for (team, number) in [(Team::White, 0), (Team::Black, 7)] {
+            let king = PieceWithTeam {
+                piece: Piece::King(RochadeAbility::Able),
+                team,
+            };
+            if self.board[number][4] != Some(king) {
+                continue;
+            }
+            for (letter, right) in [(7, 'K'), (0, 'Q')] {
+                let rook = PieceWithTeam {
+                    piece: Piece::Rook(RochadeAbility::Able),
+                    team,
+                };
+                if self.board[number][letter] == Some(rook) {
+                    rochade_rights.push(match team {
+                        Team::White => right,
+                        Team::Black => right.to_ascii_lowercase(),
+                    });
+                }
+            }
+        }
+        if rochade_rights.is_empty() {
+            rochade_rights.push('-');
+        }
+        fen.push_str(&rochade_rights);
+
+        fen.push(' ');
+        match self.en_passante_target() {
+            Some(target) => fen.push_str(&target.to_string()),
+            None => fen.push('-'),
+        }
+
+        fen.push_str(&format!(" {} {}", halfmove_clock, fullmove_number));
+        return fen;
+    }
+    pub fn from_fen(fen: &str) -> Result<Self, NotationError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 2 {
+            return Err(NotationError::Fen(
+                "expected at least the board and the turn".to_string(),
+            ));
+        }
+        let mut game_state = GameState::empty();
+
+        let rows: Vec<&str> = fields[0].split('/').collect();
+        if rows.len() != 8 {
+            return Err(NotationError::Fen(format!(
+                "expected 8 rows, found {}",
+                rows.len()
+            )));
+        }
+        for (row_index, row) in rows.iter().enumerate() {
+            let number = 7 - row_index;
+            let mut letter = 0;
+            for character in row.chars() {
+                if let Some(empty_tiles) = character.to_digit(10) {
+                    letter += empty_tiles as usize;
+                    continue;
+                }
+                let Some(piece_with_team) = PieceWithTeam::from_char(character) else {
+                    return Err(NotationError::Fen(format!("unknown piece '{}'", character)));
+                };
+                if letter > 7 {
+                    return Err(NotationError::Fen(format!(
+                        "row {} has more than 8 tiles",
+                        number + 1
+                    )));
+                }
+                game_state.board[number][letter] = Some(piece_with_team);
+                letter += 1;
+            }
+            if letter != 8 {
+                return Err(NotationError::Fen(format!(
+                    "row {} does not have 8 tiles",
+                    number + 1
+                )));
+            }
+        }
+
+        game_state.turn = match fields[1] {
+            "w" => Team::White,
+            "b" => Team::Black,
+            turn => return Err(NotationError::Fen(format!("unknown turn '{}'", turn))),
+        };
+
+        let rochade_rights = fields.get(2).copied().unwrap_or("-");
+        if rochade_rights != "-" {
+            for right in rochade_rights.chars() {
+                let (team, letter) = match right {
+                    'K' => (Team::White, 7),
+                    'Q' => (Team::White, 0),
+                    'k' => (Team::Black, 7),
+                    'q' => (Team::Black, 0),
+                    _ => {
+                        return Err(NotationError::Fen(format!(
+                            "unknown rochade right '{}'",
+                            right
+                        )))
+                    }
+                };
+                let number = match team {
+                    Team::White => 0,
+                    Team::Black => 7,
+                };
+                let king = &mut game_state.board[number][4];
+                let Some(PieceWithTeam {
+                    piece: Piece::King(ability),
+                    team: king_team,
+                }) = king
+                else {
+                    return Err(NotationError::Fen(format!(
+                        "rochade right '{}' without a king on its home tile",
+                        right
+                    )));
+                };
+                if *king_team != team {
+                    return Err(NotationError::Fen(format!(
+                        "rochade right '{}' without a king on its home tile",
+                        right
+                    )));
+                }
+                *ability = RochadeAbility::Able;
+                let rook = &mut game_state.board[number][letter];
+                let Some(PieceWithTeam {
+                    piece: Piece::Rook(ability),
+                    team: rook_team,
+                }) = rook
+                else {
+                    return Err(NotationError::Fen(format!(
+                        "rochade right '{}' without a rook in its corner",
+                        right
+                    )));
+                };
+                if *rook_team != team {
+                    return Err(NotationError::Fen(format!(
+                        "rochade right '{}' without a rook in its corner",
+                        right
+                    )));
+                }
+                *ability = RochadeAbility::Able;
+            }
+        }
+
+        let en_passante = fields.get(3).copied().unwrap_or("-");
+        if en_passante != "-" {
+            let target = TilePosition::from_notation(en_passante).map_err(|_| {
+                NotationError::Fen(format!("invalid en passante tile '{}'", en_passante))
+            })?;
+            // the pawn that just double pushed stands one tile past the target,
+            // seen from the team to move
+            let (target_number, pawn_number) = match game_state.turn {
+                Team::White => (5, 4),
+                Team::Black => (2, 3),
+            };
+            if target.number != target_number {
+                return Err(NotationError::Fen(format!(
+                    "en passante tile '{}' is on the wrong row",
+                    en_passante
+                )));
+            }
+            match &mut game_state.board[pawn_number][target.letter] {
+                Some(PieceWithTeam {
+                    piece: Piece::Pawn(vulnerability),
+                    team,
+                }) if *team == !game_state.turn => {
+                    *vulnerability = EnPassanteVulnerability::Vulnerable;
+                }
+                _ => {
+                    return Err(NotationError::Fen(format!(
+                        "en passante tile '{}' without a pawn that passed it",
+                        en_passante
+                    )))
+                }
+            }
+        }
+
+        let problems = game_state.validate();
+        if !problems.is_empty() {
+            return Err(NotationError::Position(problems));
+        }
+        return Ok(game_state);
+    }
+    // the halfmove clock and the fullmove number at the end of the fen, "0 1" when left out
+    pub fn counters_from_fen(fen: &str) -> Result<(u32, u32), NotationError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let halfmove_clock = fields.get(4).copied().unwrap_or("0");
+        let fullmove_number = fields.get(5).copied().unwrap_or("1");
+        let Ok(halfmove_clock) = halfmove_clock.parse() else {
+            return Err(NotationError::Fen(format!(
+                "invalid halfmove clock '{}'",
+                halfmove_clock
+            )));
+        };
+        let fullmove_number = match fullmove_number.parse() {
+            Ok(0) | Err(_) => {
+                return Err(NotationError::Fen(format!(
+                    "invalid fullmove number '{}'",
+                    fullmove_number
+                )))
+            }
+            Ok(fullmove_number) => fullmove_number,
+        };
+        return Ok((halfmove_clock, fullmove_number));
+    }
+    // the tile a pawn of the team to move could capture en passante on
+    pub fn en_passante_target(&self) -> Option<TilePosition> {
+        for (number, row) in self.board.iter().enumerate() {
+            for (letter, piece_option) in row.iter().enumerate() {
+                let Some(piece_with_team) = *piece_option else {
+                    continue;
+                };
+                if piece_with_team.piece != Piece::Pawn(EnPassanteVulnerability::Vulnerable) {
+                    continue;
+                }
+                let number = match piece_with_team.team {
+                    Team::White => number - 1,
+                    Team::Black => number + 1,
+                };
+                return Some(TilePosition { letter, number });
+            }
+        }
+        return None;
+    }
+}
+
+impl Play {
+    pub fn to_uci(&self) -> String {
+        let mut uci = match self.kind {
+            PlayKind::Drop(piece) => format!("{}@{}", piece.to_char(), self.target),
+            _ => format!("{}{}", self.origin, self.target),
+        };
+        if let PlayKind::Promotion(piece) = self.kind {
+            uci.push(piece.to_char().to_ascii_lowercase());
+        }
+        return uci;
+    }
+    // only plays that are possible in `game_state` are found
+    pub fn from_uci(uci: &str, game_state: &GameState) -> Result<Self, NotationError> {
+        if uci.len() != 4 && uci.len() != 5 || !uci.is_ascii() {
+            return Err(NotationError::Play(uci.to_string()));
+        }
+        let origin = TilePosition::from_notation(&uci[0..2])?;
+        let target = TilePosition::from_notation(&uci[2..4])?;
+        let promotion = match uci[4..].chars().next() {
+            Some(character) => match Piece::from_char(character) {
+                Some(piece) => Some(piece),
+                None => return Err(NotationError::Play(uci.to_string())),
+            },
+            None => None,
+        };
+        let Some(play) = Play::get_possible_plays_for_tile(origin, game_state)
+            .into_iter()
+            .find(|play| {
+                play.target == target
+                    && match play.kind {
+                        PlayKind::Promotion(piece) => {
+                            Some(piece.to_char()) == promotion.map(|piece| piece.to_char())
+                        }
+                        _ => promotion.is_none(),
+                    }
+            })
+        else {
+            return Err(NotationError::Play(format!("{} is not possible", uci)));
+        };
+        return Ok(play);
+    }
+}
+
+impl Play {
+    pub fn to_san(&self, game_state: &GameState) -> String {
+        let mut san = match self.kind {
+            PlayKind::Rochade(RochadeSide::KingSide) => "O-O".to_string(),
+            PlayKind::Rochade(RochadeSide::QueenSide) => "O-O-O".to_string(),
+            PlayKind::Drop(piece) => match piece {
+                Piece::Pawn(_) => format!("@{}", self.target),
+                _ => format!("{}@{}", piece.to_char(), self.target),
+            },
+            _ => {
+                let mut san = String::new();
+                let piece = game_state.board[self.origin.number][self.origin.letter]
+                    .map(|piece_with_team| piece_with_team.piece)
+                    .unwrap_or(Piece::Pawn(EnPassanteVulnerability::Invulnerable));
+                match piece {
+                    Piece::Pawn(_) => {
+                        if self.is_capture() {
+                            san.push((b'a' + self.origin.letter as u8) as char);
+                        }
+                    }
+                    _ => {
+                        san.push(piece.to_char());
+                        // other pieces of the same kind that could also go to the target
+                        let ambiguous: Vec<Play> = Play::get_all_possible_plays(game_state)
+                            .into_iter()
+                            .filter(|play| {
+                                play.target == self.target
+                                    && play.origin != self.origin
+                                    && game_state.board[play.origin.number][play.origin.letter]
+                                        .map(|piece_with_team| piece_with_team.piece.to_char())
+                                        == Some(piece.to_char())
+                            })
+                            .collect();
+                        if !ambiguous.is_empty() {
+                            let same_letter = ambiguous
+                                .iter()
+                                .any(|play| play.origin.letter == self.origin.letter);
+                            let same_number = ambiguous
+                                .iter()
+                                .any(|play| play.origin.number == self.origin.number);
+                            let origin = self.origin.to_string();
+                            if !same_letter {
+                                san.push_str(&origin[0..1]);
+                            } else if !same_number {
+                                san.push_str(&origin[1..2]);
+                            } else {
+                                san.push_str(&origin);
+                            }
+                        }
+                    }
+                }
+                if self.is_capture() {
+                    san.push('x');
+                }
+                san.push_str(&self.target.to_string());
+                if let PlayKind::Promotion(piece) = self.kind {
+                    san.push('=');
+                    san.push(piece.to_char());
+                }
+                san
+            }
+        };
+        let next_game_state = game_state.after(*self);
+        if next_game_state.is_in_check(next_game_state.turn) {
+            match Play::get_all_possible_plays(&next_game_state).is_empty() {
+                true => san.push('#'),
+                false => san.push('+'),
+            }
+        }
+        return san;
+    }
+    // only plays that are possible in `game_state` are found
+    pub fn from_san(san: &str, game_state: &GameState) -> Result<Self, NotationError> {
+        let simplify = |san: &str| -> String {
+            san.trim_end_matches(['+', '#', '!', '?'])
+                .replace('0', "O")
+                .replace('=', "")
+        };
+        let wanted = simplify(san.trim());
+        let Some(play) = Play::get_all_possible_plays(game_state)
+            .into_iter()
+            .find(|play| simplify(&play.to_san(game_state)) == wanted)
+        else {
+            return Err(NotationError::Play(format!("{} is not possible", san)));
+        };
+        return Ok(play);
+    }
+    // accepts both san and uci
+    pub fn from_notation(notation: &str, game_state: &GameState) -> Result<Self, NotationError> {
+        if let Ok(play) = Play::from_uci(notation, game_state) {
+            return Ok(play);
+        }
+        return Play::from_san(notation, game_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FENS: [&str; 5] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 1",
+        "rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b Kq e3 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+
+    #[test]
+    fn fen_round_trip() {
+        for fen in FENS {
+            let game_state = GameState::from_fen(fen).unwrap();
+            assert_eq!(game_state.to_fen(), fen);
+            assert_eq!(
+                GameState::from_fen(&game_state.to_fen()).unwrap(),
+                game_state
+            );
+        }
+        assert_eq!(GameState::from_fen(FENS[0]).unwrap(), GameState::new());
+    }
+
+    #[test]
+    fn en_passante_row() {
+        // the tile behind a pawn of the team to move, not the one a pawn could pass
+        let wrong_rows = [
+            "rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e4 0 1",
+            "rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e4 0 1",
+            "rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e3 0 1",
+        ];
+        for fen in wrong_rows {
+            assert!(
+                matches!(GameState::from_fen(fen), Err(NotationError::Fen(_))),
+                "{}",
+                fen
+            );
+        }
+        // without a pawn that just passed the tile
+        assert!(
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn game_counters() {
+        let mut game = crate::game::Game::new();
+        for san in [
+            "e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O", "Be7",
+        ] {
+            game.play(Play::from_san(san, game.game_state()).unwrap());
+        }
+        assert_eq!(
+            game.to_fen(),
+            "r1bqk2r/1pppbppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 w kq - 4 6"
+        );
+        game.undo();
+        assert_eq!(game.fullmove_number(), 5);
+        assert_eq!(game.halfmove_clock(), 3);
+
+        // the counters of the fen go on, with black to move
+        let fen = "rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 7 23";
+        let mut game = crate::game::Game::from_fen(fen).unwrap();
+        assert_eq!(game.to_fen(), fen);
+        game.play(Play::from_san("Nc6", game.game_state()).unwrap());
+        assert!(game.to_fen().ends_with(" 8 24"));
+        game.play(Play::from_san("Nf3", game.game_state()).unwrap());
+        game.play(Play::from_san("d3", game.game_state()).unwrap());
+        assert!(game.to_fen().ends_with(" 0 25"));
+
+        assert!(crate::game::Game::from_fen("8/8/8/8/8/8/8/K1k5 w - - 0 0").is_err());
+    }
+
+    #[test]
+    fn uci_and_san_round_trip() {
+        for fen in FENS
+            .iter()
+            .chain(["rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"].iter())
+        {
+            let game_state = GameState::from_fen(fen).unwrap();
+            for play in Play::get_all_possible_plays(&game_state) {
+                let uci = play.to_uci();
+                assert_eq!(Play::from_uci(&uci, &game_state).unwrap(), play, "{}", uci);
+                let san = play.to_san(&game_state);
+                assert_eq!(Play::from_san(&san, &game_state).unwrap(), play, "{}", san);
+                assert_eq!(Play::from_notation(&san, &game_state).unwrap(), play);
+            }
+        }
+    }
+
+    #[test]
+    fn uci_of_special_plays() {
+        let game_state = GameState::from_fen(FENS[2]).unwrap();
+        let en_passante = Play::from_uci("d5e6", &game_state).unwrap();
+        assert_eq!(en_passante.kind, PlayKind::EnPassante);
+        let game_state = GameState::from_fen(FENS[1]).unwrap();
+        let rochade = Play::from_uci("e1g1", &game_state).unwrap();
+        assert_eq!(rochade.kind, PlayKind::Rochade(RochadeSide::KingSide));
+        let game_state =
+            GameState::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8")
+                .unwrap();
+        let promotion = Play::from_uci("d7c8n", &game_state).unwrap();
+        assert_eq!(promotion.kind, PlayKind::Promotion(Piece::Horse));
+        assert_eq!(promotion.to_uci(), "d7c8n");
+        assert!(Play::from_uci("e2e5", &GameState::new()).is_err());
+    }
+}
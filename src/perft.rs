@@ -0,0 +1,96 @@
+use crate::chess::*;
+
+// how many sequences of `depth` plays there are, the usual check of the rules
+pub fn perft(game_state: &GameState, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let plays = Play::get_all_possible_plays(game_state);
+    if depth == 1 {
+        return plays.len() as u64;
+    }
+    return plays
+        .into_iter()
+        .map(|play| perft(&game_state.after(play), depth - 1))
+        .sum();
+}
+
+// the count after every first play, to find where two move generators disagree
+pub fn divide(game_state: &GameState, depth: u32) -> Vec<(Play, u64)> {
+    return Play::get_all_possible_plays(game_state)
+        .into_iter()
+        .map(|play| {
+            let count = perft(&game_state.after(play), depth.saturating_sub(1));
+            (play, count)
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the positions of the chess programming wiki
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+
+    fn count(fen: &str, depth: u32) -> u64 {
+        return perft(&GameState::from_fen(fen).unwrap(), depth);
+    }
+
+    #[test]
+    fn start_position() {
+        assert_eq!(perft(&GameState::new(), 1), 20);
+        assert_eq!(perft(&GameState::new(), 2), 400);
+        assert_eq!(perft(&GameState::new(), 3), 8902);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_eq!(count(KIWIPETE, 1), 48);
+        assert_eq!(count(KIWIPETE, 2), 2039);
+    }
+
+    #[test]
+    fn position_3() {
+        assert_eq!(count(POSITION_3, 1), 14);
+        assert_eq!(count(POSITION_3, 2), 191);
+        assert_eq!(count(POSITION_3, 3), 2812);
+    }
+
+    #[test]
+    fn position_4() {
+        assert_eq!(count(POSITION_4, 1), 6);
+        assert_eq!(count(POSITION_4, 2), 264);
+        assert_eq!(count(POSITION_4, 3), 9467);
+    }
+
+    #[test]
+    fn position_5() {
+        assert_eq!(count(POSITION_5, 1), 44);
+        assert_eq!(count(POSITION_5, 2), 1486);
+    }
+
+    #[test]
+    fn divide_adds_up() {
+        let game_state = GameState::from_fen(KIWIPETE).unwrap();
+        let divided: u64 = divide(&game_state, 2).iter().map(|(_, count)| count).sum();
+        assert_eq!(divided, 2039);
+    }
+
+    // slow without --release
+    #[test]
+    #[ignore]
+    fn deep() {
+        assert_eq!(perft(&GameState::new(), 4), 197281);
+        assert_eq!(count(KIWIPETE, 3), 97862);
+        assert_eq!(count(KIWIPETE, 4), 4085603);
+        assert_eq!(count(POSITION_3, 4), 43238);
+        assert_eq!(count(POSITION_3, 5), 674624);
+        assert_eq!(count(POSITION_4, 4), 422333);
+        assert_eq!(count(POSITION_5, 3), 62379);
+        assert_eq!(count(POSITION_5, 4), 2103487);
+    }
+}
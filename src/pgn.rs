@@ -0,0 +1,572 @@
+use crate::chess::*;
+use crate::game::{Annotation, AnnotationColor, Game, GameResult};
+use crate::notation::NotationError;
+
+// export format lines stay below 80 characters
+const LINE_LENGTH: usize = 79;
+
+impl AnnotationColor {
+    pub fn to_char(&self) -> char {
+        match self {
+            AnnotationColor::Green => 'G',
+            AnnotationColor::Red => 'R',
+            AnnotationColor::Yellow => 'Y',
+            AnnotationColor::Blue => 'B',
+        }
+    }
+    pub fn from_char(character: char) -> Option<Self> {
+        match character {
+            'G' => return Some(AnnotationColor::Green),
+            'R' => return Some(AnnotationColor::Red),
+            'Y' => return Some(AnnotationColor::Yellow),
+            'B' => return Some(AnnotationColor::Blue),
+            _ => return None,
+        }
+    }
+}
+
+impl Annotation {
+    // one entry of a command, "Ge4" is a circle and "Rg1f3" an arrow
+    pub fn from_command(command: &str) -> Result<Self, NotationError> {
+        let invalid = || NotationError::Annotation(command.to_string());
+        let mut characters = command.chars();
+        let color = characters
+            .next()
+            .and_then(AnnotationColor::from_char)
+            .ok_or_else(invalid)?;
+        let tiles = characters.as_str();
+        match tiles.len() {
+            2 => {
+                return Ok(Annotation::Circle {
+                    tile: TilePosition::from_notation(tiles).map_err(|_| invalid())?,
+                    color,
+                })
+            }
+            4 if tiles.is_char_boundary(2) => {
+                return Ok(Annotation::Arrow {
+                    origin: TilePosition::from_notation(&tiles[..2]).map_err(|_| invalid())?,
+                    target: TilePosition::from_notation(&tiles[2..]).map_err(|_| invalid())?,
+                    color,
+                })
+            }
+            _ => return Err(invalid()),
+        }
+    }
+}
+
+// "[%csl Ge4][%cal Rg1f3]", the comment commands most chess programs draw
+pub fn annotation_commands(annotations: &[Annotation]) -> String {
+    let mut circles = vec![];
+    let mut arrows = vec![];
+    for annotation in annotations {
+        match *annotation {
+            Annotation::Circle { tile, color } => {
+                circles.push(format!("{}{}", color.to_char(), tile))
+            }
+            Annotation::Arrow {
+                origin,
+                target,
+                color,
+            } => arrows.push(format!("{}{}{}", color.to_char(), origin, target)),
+        }
+    }
+    let mut commands = String::new();
+    if !circles.is_empty() {
+        commands += &format!("[%csl {}]", circles.join(","));
+    }
+    if !arrows.is_empty() {
+        commands += &format!("[%cal {}]", arrows.join(","));
+    }
+    return commands;
+}
+
+impl Game {
+    // every recorded play, also the undone ones
+    pub fn to_pgn(&self) -> String {
+        let date = self.metadata.date.as_deref().unwrap_or("????.??.??");
+        let mut tags = vec![
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", date),
+            ("Round", "?"),
+            ("White", &self.metadata.white),
+            ("Black", &self.metadata.black),
+            ("Result", self.metadata.result.to_pgn()),
+        ];
+        let fen = self.initial_fen();
+        if *self.initial_game_state() != GameState::new() {
+            tags.push(("SetUp", "1"));
+            tags.push(("FEN", &fen));
+        }
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn += &format!("[{} \"{}\"]\n", name, value);
+        }
+        pgn.push('\n');
+
+        let mut tokens = vec![];
+        let mut commented = false;
+        let commands = annotation_commands(self.annotations_at(0));
+        if !commands.is_empty() {
+            tokens.push(format!("{{{}}}", commands));
+            commented = true;
+        }
+        // a game starting with black to move starts with "1..."
+        let offset = match self.initial_game_state().turn {
+            Team::White => 0,
+            Team::Black => 1,
+        };
+        for (index, record) in self.plays().iter().enumerate() {
+            let move_number = (index + offset) / 2 + self.initial_fullmove_number() as usize;
+            match (index + offset) % 2 {
+                0 => tokens.push(format!("{}.", move_number)),
+                _ if index == 0 || commented => tokens.push(format!("{}...", move_number)),
+                _ => {}
+            }
+            tokens.push(record.san.clone());
+            let commands = annotation_commands(&record.annotations);
+            commented = !commands.is_empty();
+            if commented {
+                tokens.push(format!("{{{}}}", commands));
+            }
+        }
+        tokens.push(self.metadata.result.to_pgn().to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+                pgn += &line;
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += &token;
+        }
+        pgn += &line;
+        pgn.push('\n');
+        return pgn;
+    }
+}
+
+// the arrows and circles of the "[%csl ...]" and "[%cal ...]" commands in a comment
+pub fn parse_annotation_commands(comment: &str) -> Result<Vec<Annotation>, NotationError> {
+    let mut annotations = vec![];
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%") {
+        let Some(length) = rest[start..].find(']') else {
+            break;
+        };
+        let command = &rest[start + 2..start + length];
+        rest = &rest[start + length + 1..];
+        let Some(("csl" | "cal", entries)) = command.split_once(char::is_whitespace) else {
+            // other commands like the clock are not kept
+            continue;
+        };
+        for entry in entries.split(',') {
+            let entry = entry.trim();
+            if !entry.is_empty() {
+                annotations.push(Annotation::from_command(entry)?);
+            }
+        }
+    }
+    return Ok(annotations);
+}
+
+impl Game {
+    // the first game in the pgn, variations and other comments are skipped
+    pub fn from_pgn(pgn: &str) -> Result<Self, NotationError> {
+        let characters: Vec<char> = pgn.chars().collect();
+        let (game, _) = read_game(&characters, 0);
+        return game;
+    }
+}
+
+// every game in the pgn, a broken one does not stop the others from being read
+pub fn read_games(pgn: &str) -> Vec<Result<Game, NotationError>> {
+    let characters: Vec<char> = pgn.chars().collect();
+    let mut games = vec![];
+    let mut position = 0;
+    loop {
+        while characters.get(position).is_some_and(|c| c.is_whitespace()) {
+            position += 1;
+        }
+        if position >= characters.len() {
+            return games;
+        }
+        let (game, next) = read_game(&characters, position);
+        games.push(game);
+        // nothing could be read, the rest would not become readable either
+        if next == position {
+            return games;
+        }
+        position = next;
+    }
+}
+
+// the game starting at `position` and where the next one starts
+fn read_game(characters: &[char], mut position: usize) -> (Result<Game, NotationError>, usize) {
+    let mut tags: Vec<(String, String)> = vec![];
+    let mut game: Option<Game> = None;
+    // the first problem, the rest of the game is still read to find its end
+    let mut error: Option<NotationError> = None;
+    let mut result: Option<GameResult> = None;
+    let until = |position: usize, end: char| {
+        characters[position..]
+            .iter()
+            .position(|c| *c == end)
+            .map_or(characters.len(), |length| position + length)
+    };
+
+    while position < characters.len() {
+        let character = characters[position];
+        if character.is_whitespace() {
+            position += 1;
+            continue;
+        }
+        // tags after the moves belong to the next game
+        if character == '[' && game.is_some() {
+            break;
+        }
+        if character == '[' {
+            let end = tag_end(characters, position);
+            let tag: String = characters[position + 1..end.min(characters.len())]
+                .iter()
+                .collect();
+            match parse_tag(&tag) {
+                Some(tag) => tags.push(tag),
+                None => {
+                    error.get_or_insert(NotationError::Pgn(format!("invalid tag [{}]", tag)));
+                }
+            }
+            position = end + 1;
+            continue;
+        }
+        // everything else is part of the moves, which start in the tagged position
+        let game = game.get_or_insert_with(|| match start_game(&tags) {
+            Ok(game) => game,
+            Err(start_error) => {
+                error.get_or_insert(start_error);
+                Game::new()
+            }
+        });
+        match character {
+            '{' => {
+                let end = until(position, '}');
+                let comment: String = characters[position + 1..end].iter().collect();
+                match parse_annotation_commands(&comment) {
+                    Ok(annotations) if !annotations.is_empty() => {
+                        let mut all = game.annotations().to_vec();
+                        all.extend(annotations);
+                        game.set_annotations(all);
+                    }
+                    Ok(_) => {}
+                    Err(comment_error) => {
+                        error.get_or_insert(comment_error);
+                    }
+                }
+                position = end + 1;
+            }
+            ';' => position = until(position, '\n') + 1,
+            // a line starting with % is left out
+            '%' if position == 0 || characters[position - 1] == '\n' => {
+                position = until(position, '\n') + 1
+            }
+            '(' => position = variation_end(characters, position) + 1,
+            // closing brackets without an opening one
+            ')' | '}' | ']' => {
+                error.get_or_insert(NotationError::Pgn(format!(
+                    "unexpected '{}' after {} plays",
+                    character,
+                    game.ply()
+                )));
+                position += 1;
+            }
+            '$' => {
+                position += 1;
+                while characters.get(position).is_some_and(|c| c.is_ascii_digit()) {
+                    position += 1;
+                }
+            }
+            _ => {
+                let start = position;
+                while characters
+                    .get(position)
+                    .is_some_and(|c| !c.is_whitespace() && !"{}()[];$".contains(*c))
+                {
+                    position += 1;
+                }
+                let word: String = characters[start..position].iter().collect();
+                if let Some(game_result) = GameResult::from_pgn(&word) {
+                    result = Some(game_result);
+                    break;
+                }
+                // "12." and "12..." before the play, sometimes without a space
+                let san = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                if san.is_empty() || error.is_some() {
+                    continue;
+                }
+                match Play::from_san(san, game.game_state()) {
+                    Ok(play) => game.play(play),
+                    Err(play_error) => {
+                        error = Some(NotationError::Pgn(format!(
+                            "{} after {} plays: {}",
+                            san,
+                            game.ply(),
+                            play_error
+                        )))
+                    }
+                }
+            }
+        }
+    }
+
+    let mut game = match (game, error) {
+        (_, Some(error)) => return (Err(error), position),
+        (Some(game), None) => game,
+        (None, None) => match start_game(&tags) {
+            Ok(game) => game,
+            Err(error) => return (Err(error), position),
+        },
+    };
+    // the tag knows about resignations and agreed draws, the moves do not
+    let tagged_result = tags
+        .iter()
+        .find(|(name, _)| name == "Result")
+        .and_then(|(_, value)| GameResult::from_pgn(value));
+    if let Some(game_result) = tagged_result.or(result) {
+        if game_result != GameResult::Ongoing {
+            game.metadata.result = game_result;
+        }
+    }
+    return (Ok(game), position);
+}
+
+// the position after the closing bracket of the tag, quoted brackets do not count
+fn tag_end(characters: &[char], position: usize) -> usize {
+    let mut quoted = false;
+    let mut index = position + 1;
+    while index < characters.len() {
+        match characters[index] {
+            '\\' if quoted => index += 1,
+            '"' => quoted = !quoted,
+            ']' if !quoted => return index,
+            _ => {}
+        }
+        index += 1;
+    }
+    return characters.len();
+}
+
+// variations can hold variations and comments with brackets of their own
+fn variation_end(characters: &[char], position: usize) -> usize {
+    let mut depth = 0;
+    let mut index = position;
+    while index < characters.len() {
+        match characters[index] {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return index;
+                }
+            }
+            '{' => {
+                while index < characters.len() && characters[index] != '}' {
+                    index += 1;
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    return characters.len();
+}
+
+// `Name "value"` without the brackets
+fn parse_tag(tag: &str) -> Option<(String, String)> {
+    let (name, value) = tag.trim().split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut unescaped = String::new();
+    let mut characters = value.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => unescaped.extend(characters.next()),
+            character => unescaped.push(character),
+        }
+    }
+    return Some((name.to_string(), unescaped));
+}
+
+fn start_game(tags: &[(String, String)]) -> Result<Game, NotationError> {
+    let tag = |wanted: &str| {
+        tags.iter()
+            .find(|(name, _)| name == wanted)
+            .map(|(_, value)| value.as_str())
+    };
+    let mut game = match tag("FEN") {
+        Some(fen) => Game::from_fen(fen)?,
+        None => Game::new(),
+    };
+    if let Some(white) = tag("White") {
+        game.metadata.white = white.to_string();
+    }
+    if let Some(black) = tag("Black") {
+        game.metadata.black = black.to_string();
+    }
+    game.metadata.date = tag("Date")
+        .filter(|date| !date.contains('?'))
+        .map(|date| date.to_string());
+    return Ok(game);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sans(game: &Game) -> Vec<&str> {
+        return game
+            .plays()
+            .iter()
+            .map(|record| record.san.as_str())
+            .collect();
+    }
+
+    fn tile(notation: &str) -> TilePosition {
+        return TilePosition::from_notation(notation).unwrap();
+    }
+
+    #[test]
+    fn tags() {
+        let game = Game::from_pgn(
+            "[Event \"casual\"]\n[White \"Anna \\\"the rook\\\" B.\"]\n[Black \"Carl\"]\n\
+             [Date \"2023.03.18\"]\n[Result \"1/2-1/2\"]\n\n1. e4 e5 1/2-1/2\n",
+        )
+        .unwrap();
+        assert_eq!(game.metadata.white, "Anna \"the rook\" B.");
+        assert_eq!(game.metadata.black, "Carl");
+        assert_eq!(game.metadata.date.as_deref(), Some("2023.03.18"));
+        assert_eq!(game.metadata.result, GameResult::Draw);
+        assert_eq!(sans(&game), ["e4", "e5"]);
+
+        // an unknown date is left out
+        let game = Game::from_pgn("[Date \"????.??.??\"]\n\n*").unwrap();
+        assert_eq!(game.metadata.date, None);
+    }
+
+    #[test]
+    fn set_up_position() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2";
+        let game =
+            Game::from_pgn(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n\n2. Nf3 Nc6 *", fen)).unwrap();
+        assert_eq!(game.initial_fen(), fen);
+        assert_eq!(sans(&game), ["Nf3", "Nc6"]);
+        assert!(game.to_pgn().contains("2. Nf3 Nc6 *"));
+    }
+
+    #[test]
+    fn comments_variations_and_nags() {
+        let game = Game::from_pgn(
+            "1. e4 {the best by test} e5 ; the rest of the line is a comment 2. d4\n\
+             2. Nf3 $1 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 $14 3.Bb5 a6 *",
+        )
+        .unwrap();
+        assert_eq!(sans(&game), ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        assert_eq!(game.metadata.result, GameResult::Ongoing);
+    }
+
+    #[test]
+    fn results() {
+        let games =
+            read_games("1. f3 e5 2. g4 Qh4# 0-1\n\n1. e4 1-0\n\n[Result \"1-0\"]\n\n1. d4 *\n");
+        let results: Vec<GameResult> = games
+            .iter()
+            .map(|game| game.as_ref().unwrap().metadata.result)
+            .collect();
+        // checkmate, a resignation in the moves and one in the tag
+        assert_eq!(
+            results,
+            [
+                GameResult::BlackWins,
+                GameResult::WhiteWins,
+                GameResult::WhiteWins
+            ]
+        );
+    }
+
+    #[test]
+    fn annotation_round_trip() {
+        let mut game = Game::new();
+        game.set_annotations(vec![Annotation::Circle {
+            tile: tile("e4"),
+            color: AnnotationColor::Green,
+        }]);
+        game.play(Play::from_san("e4", game.game_state()).unwrap());
+        game.set_annotations(vec![
+            Annotation::Circle {
+                tile: tile("d5"),
+                color: AnnotationColor::Red,
+            },
+            Annotation::Arrow {
+                origin: tile("g1"),
+                target: tile("f3"),
+                color: AnnotationColor::Yellow,
+            },
+            Annotation::Arrow {
+                origin: tile("f1"),
+                target: tile("c4"),
+                color: AnnotationColor::Blue,
+            },
+        ]);
+        game.play(Play::from_san("e5", game.game_state()).unwrap());
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("{[%csl Ge4]} 1. e4 {[%csl Rd5][%cal Yg1f3,Bf1c4]} 1... e5 *"));
+        let read = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(read, game);
+        for ply in 0..=2 {
+            assert_eq!(read.annotations_at(ply), game.annotations_at(ply));
+        }
+        // other commands in the comment are not kept
+        let game = Game::from_pgn("1. e4 {[%clk 0:05:00] [%cal Ge2e4]} *").unwrap();
+        assert_eq!(
+            game.annotations(),
+            [Annotation::Arrow {
+                origin: tile("e2"),
+                target: tile("e4"),
+                color: AnnotationColor::Green,
+            }]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        for pgn in [
+            "1. e4 e5 2. Ke3 *",
+            "[White \"unterminated]\n\n1. e4 *",
+            "[FEN \"not a fen\"]\n\n1. e4 *",
+            "1. e4 {[%cal Xe2e4]} *",
+            "1. e4 ) e5 *",
+            "1. e4 } e5 *",
+            "1. e4 ] e5 *",
+        ] {
+            assert!(
+                matches!(
+                    Game::from_pgn(pgn),
+                    Err(NotationError::Pgn(_)
+                        | NotationError::Fen(_)
+                        | NotationError::Annotation(_))
+                ),
+                "{}",
+                pgn
+            );
+        }
+        // a broken game does not stop the next one
+        let games = read_games("1. e4 ) e5 *\n\n1. d4 d5 *\n");
+        assert_eq!(games.len(), 2);
+        assert!(games[0].is_err());
+        assert_eq!(sans(games[1].as_ref().unwrap()), ["d4", "d5"]);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::chess::*;
+
+// the embedded pictures, with the names of their files in png/
+pub const BOARD_PICTURE: (&str, &[u8]) = ("schach.png", include_bytes!("../png/schach.png"));
+
+pub fn piece_picture(piece_with_team: PieceWithTeam) -> (&'static str, &'static [u8]) {
+    match (piece_with_team.piece, piece_with_team.team) {
+        (Piece::King(_), Team::White) => {
+            return (
+                "Chess_klt45.png",
+                include_bytes!("../png/Chess_klt45.png").as_slice(),
+            )
+        }
+        (Piece::King(_), Team::Black) => {
+            return (
+                "Chess_kdt45.png",
+                include_bytes!("../png/Chess_kdt45.png").as_slice(),
+            )
+        }
+        (Piece::Queen, Team::White) => {
+            return (
+                "Chess_qlt45.png",
+                include_bytes!("../png/Chess_qlt45.png").as_slice(),
+            )
+        }
+        (Piece::Queen, Team::Black) => {
+            return (
+                "Chess_qdt45.png",
+                include_bytes!("../png/Chess_qdt45.png").as_slice(),
+            )
+        }
+        (Piece::Rook(_), Team::White) => {
+            return (
+                "Chess_rlt45.png",
+                include_bytes!("../png/Chess_rlt45.png").as_slice(),
+            )
+        }
+        (Piece::Rook(_), Team::Black) => {
+            return (
+                "Chess_rdt45.png",
+                include_bytes!("../png/Chess_rdt45.png").as_slice(),
+            )
+        }
+        (Piece::Bishop, Team::White) => {
+            return (
+                "Chess_blt45.png",
+                include_bytes!("../png/Chess_blt45.png").as_slice(),
+            )
+        }
+        (Piece::Bishop, Team::Black) => {
+            return (
+                "Chess_bdt45.png",
+                include_bytes!("../png/Chess_bdt45.png").as_slice(),
+            )
+        }
+        (Piece::Horse, Team::White) => {
+            return (
+                "Chess_nlt45.png",
+                include_bytes!("../png/Chess_nlt45.png").as_slice(),
+            )
+        }
+        (Piece::Horse, Team::Black) => {
+            return (
+                "Chess_ndt45.png",
+                include_bytes!("../png/Chess_ndt45.png").as_slice(),
+            )
+        }
+        (Piece::Pawn(_), Team::White) => {
+            return (
+                "Chess_plt45.png",
+                include_bytes!("../png/Chess_plt45.png").as_slice(),
+            )
+        }
+        (Piece::Pawn(_), Team::Black) => {
+            return (
+                "Chess_pdt45.png",
+                include_bytes!("../png/Chess_pdt45.png").as_slice(),
+            )
+        }
+    }
+}
+
+// one of each piece, the white ones first
+pub fn all_pieces() -> Vec<PieceWithTeam> {
+    let mut pieces = vec![];
+    for team in [Team::White, Team::Black] {
+        for piece in [
+            Piece::King(RochadeAbility::Unable),
+            Piece::Queen,
+            Piece::Rook(RochadeAbility::Unable),
+            Piece::Bishop,
+            Piece::Horse,
+            Piece::Pawn(EnPassanteVulnerability::Invulnerable),
+        ] {
+            pieces.push(PieceWithTeam { piece, team });
+        }
+    }
+    return pieces;
+}
+
+// what the window, the pngs and the svgs draw over the board, the same everywhere
+pub const HIGHLIGHT: [u8; 4] = [220, 40, 40, 110];
+// sizes of the annotations in tiles, the circle radius is to its outer edge
+pub const SHAFT_WIDTH: f32 = 0.15;
+pub const HEAD_LENGTH: f32 = 0.4;
+pub const HEAD_WIDTH: f32 = 0.5;
+pub const CIRCLE_RADIUS: f32 = 0.46;
+pub const CIRCLE_WIDTH: f32 = 0.07;
+
+// the letters and numbers along the edges, dark on the light tiles and the other way around
+pub fn coordinate_color(tile: TilePosition) -> [u8; 4] {
+    match (tile.letter + tile.number) % 2 {
+        0 => return [242, 242, 242, 217],
+        _ => return [38, 38, 38, 217],
+    }
+}
+
+// every tile once, tiles in the list twice would be drawn darker
+pub fn distinct_tiles(tiles: &[TilePosition]) -> Vec<TilePosition> {
+    let mut distinct = vec![];
+    for tile in tiles {
+        if !distinct.contains(tile) {
+            distinct.push(*tile);
+        }
+    }
+    return distinct;
+}
+
+// the corners of an arrow between the centers `start` and `end`, going around from the side of
+// the start, in whatever unit `tile_size` is
+pub fn arrow_outline(start: [f32; 2], end: [f32; 2], tile_size: f32) -> [[f32; 2]; 7] {
+    let length = ((end[0] - start[0]).powi(2) + (end[1] - start[1]).powi(2)).sqrt();
+    let direction = [(end[0] - start[0]) / length, (end[1] - start[1]) / length];
+    let normal = [-direction[1], direction[0]];
+    let at = |from: [f32; 2], along: f32, across: f32| {
+        [
+            from[0] + direction[0] * along + normal[0] * across,
+            from[1] + direction[1] * along + normal[1] * across,
+        ]
+    };
+    let shaft = SHAFT_WIDTH * 0.5 * tile_size;
+    let head = HEAD_WIDTH * 0.5 * tile_size;
+    let shaft_end = at(end, -HEAD_LENGTH * tile_size, 0.0);
+    return [
+        at(start, 0.0, shaft),
+        at(shaft_end, 0.0, shaft),
+        at(shaft_end, 0.0, head),
+        end,
+        at(shaft_end, 0.0, -head),
+        at(shaft_end, 0.0, -shaft),
+        at(start, 0.0, -shaft),
+    ];
+}
+
+// one of the pictures, or a png of a theme, with its colors as rgba
+#[cfg(feature = "render")]
+pub fn decode_png(bytes: &[u8]) -> Option<image::RgbaImage> {
+    let image = image::load(std::io::Cursor::new(bytes), image::ImageFormat::Png).ok()?;
+    return Some(image.to_rgba8());
+}
@@ -0,0 +1,345 @@
+use crate::chess::*;
+use crate::font::{glyph_index, FONT};
+use crate::game::Game;
+use crate::pictures::{
+    all_pieces, coordinate_color, decode_png, distinct_tiles, piece_picture, BOARD_PICTURE,
+    HIGHLIGHT,
+};
+use image::{imageops, Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::time::Duration;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    // black at the bottom
+    pub flipped: bool,
+    // letters along the bottom and numbers along the left edge
+    pub coordinates: bool,
+    pub highlights: Vec<TilePosition>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GifOptions {
+    pub size: u32,
+    pub flipped: bool,
+    pub coordinates: bool,
+    // how long every position is shown
+    pub delay: Duration,
+    // how much longer the last position stays before the game starts over
+    pub final_hold: Duration,
+    // the tiles of the last play, like in the window
+    pub highlight_last_play: bool,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        return Self {
+            size: 400,
+            flipped: false,
+            coordinates: true,
+            delay: Duration::from_secs(1),
+            final_hold: Duration::from_secs(3),
+            highlight_last_play: true,
+        };
+    }
+}
+
+// draws positions without opengl, the pictures are scaled once for all of them
+pub struct Renderer {
+    size: u32,
+    board: RgbaImage,
+    // by the letter of the piece in fen
+    pieces: HashMap<char, RgbaImage>,
+}
+
+impl Renderer {
+    // `size` is the width and the height of the pictures in pixels
+    pub fn new(size: u32) -> Self {
+        let size = size.max(8);
+        let tile_size = size / 8;
+        let board = imageops::resize(
+            &decode_png(BOARD_PICTURE.1).unwrap(),
+            size,
+            size,
+            imageops::FilterType::Triangle,
+        );
+        let mut pieces = HashMap::new();
+        for piece_with_team in all_pieces() {
+            let (_, bytes) = piece_picture(piece_with_team);
+            let picture = imageops::resize(
+                &decode_png(bytes).unwrap(),
+                tile_size,
+                tile_size,
+                imageops::FilterType::CatmullRom,
+            );
+            pieces.insert(piece_with_team.to_char(), picture);
+        }
+        return Self {
+            size,
+            board,
+            pieces,
+        };
+    }
+    pub fn size(&self) -> u32 {
+        return self.size;
+    }
+    pub fn render(&self, game_state: &GameState, options: &RenderOptions) -> RgbaImage {
+        let mut picture = self.board.clone();
+        for tile in distinct_tiles(&options.highlights) {
+            let [left, top, right, bottom] = self.tile_rect(tile, options.flipped);
+            for y in top..bottom {
+                for x in left..right {
+                    blend(picture.get_pixel_mut(x, y), HIGHLIGHT);
+                }
+            }
+        }
+        for (number, row) in game_state.board.iter().enumerate() {
+            for (letter, piece_option) in row.iter().enumerate() {
+                let Some(piece_with_team) = piece_option else {
+                    continue;
+                };
+                let [left, top, _, _] =
+                    self.tile_rect(TilePosition { letter, number }, options.flipped);
+                let piece = &self.pieces[&piece_with_team.to_char()];
+                imageops::overlay(&mut picture, piece, left as i64, top as i64);
+            }
+        }
+        if options.coordinates {
+            self.draw_coordinates(&mut picture, options.flipped);
+        }
+        return picture;
+    }
+    // left, top, right and bottom in pixels of the tile as it is seen
+    fn tile_rect(&self, tile: TilePosition, flipped: bool) -> [u32; 4] {
+        let tile = tile.oriented(flipped);
+        let edge = |index: usize| index as u32 * self.size / 8;
+        return [
+            edge(tile.letter),
+            edge(7 - tile.number),
+            edge(tile.letter + 1),
+            edge(8 - tile.number),
+        ];
+    }
+    // placed like in the window
+    fn draw_coordinates(&self, picture: &mut RgbaImage, flipped: bool) {
+        let tile_size = self.size / 8;
+        let scale = (tile_size / 40).max(1);
+        let padding = scale * 2;
+        for index in 0..8 {
+            // the tile in the bottom row and the one in the left column
+            let bottom = TilePosition {
+                letter: index,
+                number: 0,
+            }
+            .oriented(flipped);
+            let left = TilePosition {
+                letter: 0,
+                number: index,
+            }
+            .oriented(flipped);
+            let [_, _, right, bottom_edge] = self.tile_rect(bottom, flipped);
+            draw_glyph(
+                picture,
+                (b'a' + bottom.letter as u8) as char,
+                [
+                    right.saturating_sub(6 * scale + padding),
+                    bottom_edge.saturating_sub(8 * scale + padding / 2),
+                ],
+                scale,
+                coordinate_color(bottom),
+            );
+            let [left_edge, top, _, _] = self.tile_rect(left, flipped);
+            draw_glyph(
+                picture,
+                (b'1' + left.number as u8) as char,
+                [left_edge + padding, top + padding],
+                scale,
+                coordinate_color(left),
+            );
+        }
+    }
+}
+
+// the top left corner in pixels, `scale` pixels per font pixel
+fn draw_glyph(
+    picture: &mut RgbaImage,
+    character: char,
+    position: [u32; 2],
+    scale: u32,
+    color: [u8; 4],
+) {
+    let [x, y] = position;
+    for (column, bits) in FONT[glyph_index(character)].iter().enumerate() {
+        for row in 0..7 {
+            if bits & (1 << row) == 0 {
+                continue;
+            }
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let pixel_x = x + column as u32 * scale + dx;
+                    let pixel_y = y + row * scale + dy;
+                    if pixel_x < picture.width() && pixel_y < picture.height() {
+                        blend(picture.get_pixel_mut(pixel_x, pixel_y), color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// `color` over the pixel, by its alpha
+fn blend(pixel: &mut Rgba<u8>, color: [u8; 4]) {
+    let alpha = color[3] as u32;
+    for channel in 0..3 {
+        pixel[channel] =
+            ((color[channel] as u32 * alpha + pixel[channel] as u32 * (255 - alpha)) / 255) as u8;
+    }
+    pixel[3] = (alpha + pixel[3] as u32 * (255 - alpha) / 255) as u8;
+}
+
+// the picture encoded as a png file
+pub fn encode_png(picture: &RgbaImage) -> Result<Vec<u8>, image::ImageError> {
+    let mut bytes = Cursor::new(vec![]);
+    picture.write_to(&mut bytes, image::ImageOutputFormat::Png)?;
+    return Ok(bytes.into_inner());
+}
+
+// a png of the position, `size` pixels wide and high
+pub fn render_png(
+    game_state: &GameState,
+    size: u32,
+    options: &RenderOptions,
+) -> Result<Vec<u8>, image::ImageError> {
+    return encode_png(&Renderer::new(size).render(game_state, options));
+}
+
+impl Game {
+    // an animated gif of every recorded play, starting with the initial position
+    pub fn to_gif(&self, options: &GifOptions) -> Result<Vec<u8>, image::ImageError> {
+        let renderer = Renderer::new(options.size);
+        let mut positions = vec![(*self.initial_game_state(), vec![])];
+        for record in self.plays() {
+            let highlights = match options.highlight_last_play {
+                true => vec![record.play.origin, record.play.target],
+                false => vec![],
+            };
+            positions.push((record.game_state, highlights));
+        }
+        let last = positions.len() - 1;
+        let mut frames = vec![];
+        for (index, (game_state, highlights)) in positions.into_iter().enumerate() {
+            let picture = renderer.render(
+                &game_state,
+                &RenderOptions {
+                    flipped: options.flipped,
+                    coordinates: options.coordinates,
+                    highlights,
+                },
+            );
+            let delay = match index == last {
+                true => options.delay + options.final_hold,
+                false => options.delay,
+            };
+            frames.push(image::Frame::from_parts(
+                picture,
+                0,
+                0,
+                image::Delay::from_saturating_duration(delay),
+            ));
+        }
+
+        let mut bytes = vec![];
+        {
+            // the slowest speeds do not look better with the few colors of a board
+            let mut encoder = image::codecs::gif::GifEncoder::new_with_speed(&mut bytes, 10);
+            encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+            encoder.encode_frames(frames)?;
+        }
+        return Ok(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pictures::decode_png;
+
+    // rerun with UPDATE_GOLDEN=1 after changing how positions are drawn on purpose
+    fn compare(name: &str, game_state: &GameState, options: &RenderOptions) {
+        let path = format!("{}/tests/golden/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+        let png = render_png(game_state, 200, options).unwrap();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, &png).unwrap();
+            return;
+        }
+        let expected = decode_png(&std::fs::read(&path).unwrap()).unwrap();
+        let actual = decode_png(&png).unwrap();
+        assert_eq!(actual.dimensions(), expected.dimensions(), "{}", name);
+        // a little room for the rounding of other platforms when scaling the pictures
+        let different = actual
+            .pixels()
+            .zip(expected.pixels())
+            .filter(|(actual, expected)| {
+                actual
+                    .0
+                    .iter()
+                    .zip(expected.0)
+                    .any(|(a, b)| a.abs_diff(b) > 2)
+            })
+            .count();
+        assert_eq!(different, 0, "{} differs from {}", name, path);
+    }
+
+    fn tiles(notations: &[&str]) -> Vec<TilePosition> {
+        return notations
+            .iter()
+            .map(|notation| TilePosition::from_notation(notation).unwrap())
+            .collect();
+    }
+
+    #[test]
+    fn start_position() {
+        compare("startpos", &GameState::new(), &RenderOptions::default());
+    }
+
+    #[test]
+    fn flipped() {
+        let game_state =
+            GameState::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 1")
+                .unwrap();
+        let options = RenderOptions {
+            flipped: true,
+            ..RenderOptions::default()
+        };
+        compare("flipped", &game_state, &options);
+    }
+
+    #[test]
+    fn highlights() {
+        let game_state =
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+                .unwrap();
+        // the same tile twice is drawn once
+        let options = RenderOptions {
+            highlights: tiles(&["e2", "e4", "e4"]),
+            ..RenderOptions::default()
+        };
+        compare("highlights", &game_state, &options);
+    }
+
+    #[test]
+    fn coordinates() {
+        let options = RenderOptions {
+            coordinates: true,
+            ..RenderOptions::default()
+        };
+        compare("coordinates", &GameState::new(), &options);
+        let options = RenderOptions {
+            flipped: true,
+            coordinates: true,
+            highlights: tiles(&["a1", "h8"]),
+        };
+        compare("coordinates_flipped", &GameState::new(), &options);
+    }
+}
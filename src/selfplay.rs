@@ -0,0 +1,496 @@
+use crate::chess::*;
+use crate::game::{Game, GameResult};
+use crate::solver::{self, SearchLimits, SearchResult, MATE};
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+// one side of a match, the solver of this build or another deep_red program
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Engine {
+    Solver(SearchLimits),
+    // asked with `PROGRAM bestmove FEN --depth N --time SECONDS` for every play,
+    // so an older build of deep_red can play against the current one
+    Program { path: PathBuf, limits: SearchLimits },
+}
+
+impl Engine {
+    pub fn limits(&self) -> SearchLimits {
+        match self {
+            Engine::Solver(limits) | Engine::Program { limits, .. } => return *limits,
+        }
+    }
+    // for the pgn tags, like "deep_red depth 4"
+    pub fn name(&self) -> String {
+        let limits = self.limits();
+        let mut name = match self {
+            Engine::Solver(_) => "deep_red".to_string(),
+            Engine::Program { path, .. } => path.display().to_string(),
+        };
+        name += &format!(" depth {}", limits.depth);
+        if let Some(time) = limits.time {
+            name += &format!(" time {}", time.as_secs_f64());
+        }
+        return name;
+    }
+    pub fn search(&self, game_state: &GameState) -> io::Result<SearchResult> {
+        let Engine::Program { path, limits } = self else {
+            return Ok(solver::search(game_state, self.limits()));
+        };
+        let mut command = Command::new(path);
+        command
+            .arg("bestmove")
+            .arg(game_state.to_fen())
+            .arg("--depth")
+            .arg(limits.depth.to_string());
+        if let Some(time) = limits.time {
+            command.arg("--time").arg(time.as_secs_f64().to_string());
+        }
+        let output = command.output()?;
+        let error = |message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), message),
+            )
+        };
+        if !output.status.success() {
+            return Err(error(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+        let mut result = SearchResult {
+            play: None,
+            evaluation: 0,
+            depth: 0,
+            nodes: 0,
+        };
+        let mut answered = false;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["bestmove", "(none)"] => answered = true,
+                ["bestmove", play] => {
+                    let play = Play::from_notation(play, game_state)
+                        .map_err(|notation_error| error(notation_error.to_string()))?;
+                    result.play = Some(play);
+                    answered = true;
+                }
+                ["info", rest @ ..] => {
+                    for pair in rest.windows(2) {
+                        match pair {
+                            ["depth", depth] => result.depth = depth.parse().unwrap_or(0),
+                            ["nodes", nodes] => result.nodes = nodes.parse().unwrap_or(0),
+                            ["cp", centipawns] => {
+                                result.evaluation = centipawns.parse().unwrap_or(0)
+                            }
+                            // the evaluation the solver gives a mate in that many moves
+                            ["mate", moves] => {
+                                let moves: i32 = moves.parse().unwrap_or(0);
+                                result.evaluation = moves.signum() * (MATE - 2 * moves.abs() + 1);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !answered {
+            return Err(error("no bestmove in the answer".to_string()));
+        }
+        return Ok(result);
+    }
+}
+
+// when a game is stopped before the end, by evaluations from the view of white
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Adjudication {
+    // a win once both engines agree on at least this many centipawns for `win_plays` plays in a row
+    pub win_score: i32,
+    pub win_plays: usize,
+    // a draw once both engines are within this many centipawns of 0 for `draw_plays` plays in a row,
+    // but not before `draw_after` plays
+    pub draw_score: i32,
+    pub draw_plays: usize,
+    pub draw_after: usize,
+    // a draw after this many plays in total, to keep long endgames from going on forever
+    pub max_plays: usize,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        return Self {
+            win_score: 1000,
+            win_plays: 8,
+            draw_score: 10,
+            draw_plays: 16,
+            draw_after: 80,
+            max_plays: 400,
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ending {
+    Checkmate,
+    Stalemate,
+    Repetition,
+    // fifty moves of each team without a capture or a pawn moving
+    FiftyMoves,
+    InsufficientMaterial,
+    AdjudicatedWin,
+    AdjudicatedDraw,
+    MaxPlays,
+}
+
+impl Ending {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Ending::Checkmate => return "checkmate",
+            Ending::Stalemate => return "stalemate",
+            Ending::Repetition => return "threefold repetition",
+            Ending::FiftyMoves => return "fifty move rule",
+            Ending::InsufficientMaterial => return "insufficient material",
+            Ending::AdjudicatedWin => return "adjudicated win",
+            Ending::AdjudicatedDraw => return "adjudicated draw",
+            Ending::MaxPlays => return "too many plays",
+        }
+    }
+}
+
+// plays one game from `opening`, the result is in the metadata of the game
+pub fn play_game(
+    white: &Engine,
+    black: &Engine,
+    opening: &GameState,
+    adjudication: &Adjudication,
+) -> io::Result<(Game, Ending)> {
+    let mut game = Game::from_game_state(*opening);
+    game.metadata.white = white.name();
+    game.metadata.black = black.name();
+    // plays in a row for which the adjudication conditions held
+    let mut win_streak = 0;
+    let mut winning_team = None;
+    let mut draw_streak = 0;
+    let mut reversible_plays = 0;
+    let ending = loop {
+        let game_state = *game.game_state();
+        match GameResult::of(&game_state) {
+            GameResult::WhiteWins | GameResult::BlackWins => break Ending::Checkmate,
+            GameResult::Draw => break Ending::Stalemate,
+            GameResult::Ongoing => {}
+        }
+        if insufficient_material(&game_state) {
+            break Ending::InsufficientMaterial;
+        }
+        if reversible_plays >= 100 {
+            break Ending::FiftyMoves;
+        }
+        let positions = &game.plays()[..game.ply()];
+        let repetitions = positions
+            .iter()
+            .filter(|record| record.game_state == game_state)
+            .count()
+            + (*game.initial_game_state() == game_state) as usize;
+        if repetitions >= 3 {
+            break Ending::Repetition;
+        }
+        if game.ply() >= adjudication.max_plays {
+            break Ending::MaxPlays;
+        }
+
+        let engine = match game_state.turn {
+            Team::White => white,
+            Team::Black => black,
+        };
+        let result = engine.search(&game_state)?;
+        let Some(play) = result.play else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} found no play in {}", engine.name(), game_state.to_fen()),
+            ));
+        };
+        if !Play::get_all_possible_plays(&game_state).contains(&play) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} played {} in {}",
+                    engine.name(),
+                    play.to_uci(),
+                    game_state.to_fen()
+                ),
+            ));
+        }
+
+        let evaluation = match game_state.turn {
+            Team::White => result.evaluation,
+            Team::Black => -result.evaluation,
+        };
+        let leader = match evaluation >= 0 {
+            true => Team::White,
+            false => Team::Black,
+        };
+        match evaluation.abs() >= adjudication.win_score {
+            true if winning_team == Some(leader) => win_streak += 1,
+            true => {
+                win_streak = 1;
+                winning_team = Some(leader);
+            }
+            false => {
+                win_streak = 0;
+                winning_team = None;
+            }
+        }
+        match evaluation.abs() <= adjudication.draw_score {
+            true => draw_streak += 1,
+            false => draw_streak = 0,
+        }
+
+        let moving_pawn = matches!(
+            game_state.board[play.origin.number][play.origin.letter],
+            Some(PieceWithTeam {
+                piece: Piece::Pawn(_),
+                ..
+            })
+        );
+        match play.captured.is_some() || moving_pawn {
+            true => reversible_plays = 0,
+            false => reversible_plays += 1,
+        }
+        game.play(play);
+
+        if win_streak >= adjudication.win_plays && game.metadata.result == GameResult::Ongoing {
+            game.metadata.result = match winning_team {
+                Some(Team::White) => GameResult::WhiteWins,
+                _ => GameResult::BlackWins,
+            };
+            break Ending::AdjudicatedWin;
+        }
+        if draw_streak >= adjudication.draw_plays
+            && game.ply() >= adjudication.draw_after
+            && game.metadata.result == GameResult::Ongoing
+        {
+            break Ending::AdjudicatedDraw;
+        }
+    };
+    if game.metadata.result == GameResult::Ongoing {
+        game.metadata.result = GameResult::Draw;
+    }
+    return Ok((game, ending));
+}
+
+// no way to checkmate: kings alone or with one bishop or horse
+fn insufficient_material(game_state: &GameState) -> bool {
+    let mut minor_pieces = 0;
+    for piece_with_team in game_state.board.iter().flatten().flatten() {
+        match piece_with_team.piece {
+            Piece::King(_) => {}
+            Piece::Bishop | Piece::Horse => minor_pieces += 1,
+            _ => return false,
+        }
+    }
+    return minor_pieces <= 1;
+}
+
+// wins, draws and losses of the first engine
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        return self.wins + self.draws + self.losses;
+    }
+    // the points per game, 1 for winning every game
+    pub fn score(&self) -> f64 {
+        return (self.wins as f64 + self.draws as f64 / 2.0) / self.games().max(1) as f64;
+    }
+    // how much the points of one game vary around the score
+    fn variance(&self) -> f64 {
+        let score = self.score();
+        let squares = self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2);
+        return squares / self.games().max(1) as f64;
+    }
+    // the elo difference and half the width of its 95% confidence interval,
+    // infinite while one engine won or lost everything
+    pub fn elo(&self) -> (f64, f64) {
+        let score = self.score();
+        if self.games() == 0 {
+            return (0.0, f64::INFINITY);
+        }
+        // the interval would go from infinity to infinity
+        if score == 0.0 || score == 1.0 {
+            return (elo_of(score), f64::INFINITY);
+        }
+        let error = (self.variance() / self.games().max(1) as f64).sqrt();
+        let low = elo_of(score - 1.96 * error);
+        let high = elo_of(score + 1.96 * error);
+        return (elo_of(score), (high - low) / 2.0);
+    }
+    // the log likelihood ratio of the engines being `elo1` rather than `elo0` apart,
+    // with the normal approximation of the generalized sprt
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let variance = self.variance();
+        if self.games() == 0 || variance == 0.0 {
+            return 0.0;
+        }
+        let score0 = score_of(elo0);
+        let score1 = score_of(elo1);
+        return self.games() as f64 * (score1 - score0) * (2.0 * self.score() - score0 - score1)
+            / (2.0 * variance);
+    }
+}
+
+fn elo_of(score: f64) -> f64 {
+    let score = score.clamp(0.0, 1.0);
+    return 400.0 * (score / (1.0 - score)).log10();
+}
+
+fn score_of(elo: f64) -> f64 {
+    return 1.0 / (1.0 + 10f64.powf(-elo / 400.0));
+}
+
+// is the first engine at least `elo1` better, or not better than `elo0`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    // the chances of accepting elo1 when elo0 is true and the other way around
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtVerdict {
+    // the first engine is not better by elo0
+    AcceptElo0,
+    // the first engine is better by elo1
+    AcceptElo1,
+    Continue,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        return Self {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+    }
+}
+
+impl Sprt {
+    // the llr at which elo0 and elo1 are accepted
+    pub fn bounds(&self) -> (f64, f64) {
+        return (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        );
+    }
+    pub fn verdict(&self, score: &MatchScore) -> SprtVerdict {
+        let llr = score.llr(self.elo0, self.elo1);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            return SprtVerdict::AcceptElo0;
+        }
+        if llr >= upper {
+            return SprtVerdict::AcceptElo1;
+        }
+        return SprtVerdict::Continue;
+    }
+}
+
+// a few usual openings, so two deterministic engines do not play the same game over and over
+pub const OPENINGS: [&str; 12] = [
+    "e4 e5 Nf3 Nc6 Bb5 a6",
+    "e4 e5 Nf3 Nc6 Bc4 Bc5",
+    "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6",
+    "e4 c5 Nc3 Nc6 g3 g6",
+    "e4 e6 d4 d5 Nc3 Bb4",
+    "e4 c6 d4 d5 e5 Bf5",
+    "d4 d5 c4 e6 Nc3 Nf6",
+    "d4 d5 c4 c6 Nf3 Nf6",
+    "d4 Nf6 c4 g6 Nc3 Bg7 e4 d6",
+    "d4 Nf6 c4 e6 Nc3 Bb4",
+    "c4 e5 Nc3 Nf6 g3 d5",
+    "Nf3 d5 g3 Nf6 Bg2 c6",
+];
+
+// the positions after the plays of `OPENINGS`
+pub fn default_openings() -> Vec<GameState> {
+    return OPENINGS
+        .iter()
+        .map(|plays| {
+            let mut game_state = GameState::new();
+            for san in plays.split_whitespace() {
+                game_state = game_state.after(Play::from_san(san, &game_state).unwrap());
+            }
+            game_state
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(wins: u32, draws: u32, losses: u32) -> MatchScore {
+        return MatchScore {
+            wins,
+            draws,
+            losses,
+        };
+    }
+
+    fn assert_close(value: f64, expected: f64) {
+        assert!((value - expected).abs() < 1e-6, "{} != {}", value, expected);
+    }
+
+    #[test]
+    fn elo() {
+        let (elo, margin) = score(10, 0, 10).elo();
+        assert_close(elo, 0.0);
+        assert_close(margin, 163.324830);
+        let (elo, margin) = score(15, 0, 5).elo();
+        assert_close(elo, 190.848502);
+        assert_close(margin, 217.625261);
+        let (elo, margin) = score(60, 20, 40).elo();
+        assert_close(elo, 58.451214);
+        assert_close(margin, 57.964743);
+        assert_close(score(0, 7, 0).elo().0, 0.0);
+    }
+
+    #[test]
+    fn elo_of_one_sided_matches() {
+        assert_eq!(score(5, 0, 0).elo(), (f64::INFINITY, f64::INFINITY));
+        assert_eq!(score(0, 0, 5).elo(), (f64::NEG_INFINITY, f64::INFINITY));
+        assert_eq!(score(0, 0, 0).elo(), (0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn llr() {
+        assert_close(score(60, 20, 40).llr(0.0, 10.0), 0.652729);
+        assert_close(score(600, 200, 400).llr(0.0, 10.0), 6.527290);
+        assert_close(score(400, 200, 600).llr(0.0, 10.0), -7.760672);
+        assert_close(score(100, 800, 100).llr(0.0, 10.0), -2.069911);
+        // nothing is known without games or without any variance
+        assert_eq!(score(0, 0, 0).llr(0.0, 10.0), 0.0);
+        assert_eq!(score(0, 9, 0).llr(0.0, 10.0), 0.0);
+    }
+
+    #[test]
+    fn sprt_verdict() {
+        let sprt = Sprt::default();
+        let (lower, upper) = sprt.bounds();
+        assert_close(lower, -2.944439);
+        assert_close(upper, 2.944439);
+        assert_eq!(sprt.verdict(&score(60, 20, 40)), SprtVerdict::Continue);
+        assert_eq!(sprt.verdict(&score(600, 200, 400)), SprtVerdict::AcceptElo1);
+        assert_eq!(sprt.verdict(&score(400, 200, 600)), SprtVerdict::AcceptElo0);
+        assert_eq!(sprt.verdict(&score(100, 800, 100)), SprtVerdict::Continue);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::chess::*;
+use crate::game::{Annotation, Game, GameMetadata};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+// tiles are written like "e4", positions as fen
+
+impl Serialize for TilePosition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+impl<'de> Deserialize<'de> for TilePosition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let notation = String::deserialize(deserializer)?;
+        return TilePosition::from_notation(&notation).map_err(D::Error::custom);
+    }
+}
+
+impl Serialize for GameState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(&self.to_fen());
+    }
+}
+
+impl<'de> Deserialize<'de> for GameState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+        return GameState::from_fen(&fen).map_err(D::Error::custom);
+    }
+}
+
+// plays on their own are written with all their fields, since uci needs the position to be read
+// again; games keep them in uci instead. the fields have to fit together, a capture needs a
+// captured piece and a quiet play can not have one
+#[derive(Deserialize)]
+#[serde(rename = "Play")]
+struct PlayFields {
+    origin: TilePosition,
+    target: TilePosition,
+    kind: PlayKind,
+    captured: Option<PieceWithTeam>,
+}
+
+impl<'de> Deserialize<'de> for Play {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = PlayFields::deserialize(deserializer)?;
+        let captured_piece = fields.captured.map(|captured| captured.piece);
+        let fitting = match fields.kind {
+            PlayKind::Quiet | PlayKind::Rochade(_) | PlayKind::Drop(_) => captured_piece.is_none(),
+            PlayKind::DoublePush => {
+                captured_piece.is_none()
+                    && fields.origin.letter == fields.target.letter
+                    && fields.origin.number.abs_diff(fields.target.number) == 2
+            }
+            PlayKind::Capture => captured_piece.is_some(),
+            PlayKind::EnPassante => matches!(captured_piece, Some(Piece::Pawn(_))),
+            PlayKind::Promotion(piece) => !matches!(piece, Piece::King(_) | Piece::Pawn(_)),
+        };
+        if !fitting {
+            return Err(D::Error::custom(format!(
+                "a {:?} play can not capture {:?}",
+                fields.kind, fields.captured
+            )));
+        }
+        if matches!(captured_piece, Some(Piece::King(_))) {
+            return Err(D::Error::custom("kings are never captured"));
+        }
+        return Ok(Play {
+            origin: fields.origin,
+            target: fields.target,
+            kind: fields.kind,
+            captured: fields.captured,
+        });
+    }
+}
+
+// games keep their plays in uci, the rest is recomputed by replaying them
+#[derive(Serialize, Deserialize)]
+struct GameRecord {
+    metadata: GameMetadata,
+    initial_game_state: GameState,
+    plays: Vec<String>,
+    ply: usize,
+    // for every ply from the initial position on, left out without any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Vec<Annotation>>,
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return GameRecord {
+            metadata: self.metadata.clone(),
+            initial_game_state: *self.initial_game_state(),
+            plays: self
+                .plays()
+                .iter()
+                .map(|record| record.play.to_uci())
+                .collect(),
+            ply: self.ply(),
+            annotations: match (0..=self.plays().len())
+                .all(|ply| self.annotations_at(ply).is_empty())
+            {
+                true => vec![],
+                false => (0..=self.plays().len())
+                    .map(|ply| self.annotations_at(ply).to_vec())
+                    .collect(),
+            },
+        }
+        .serialize(serializer);
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = GameRecord::deserialize(deserializer)?;
+        let mut game = Game::from_game_state(record.initial_game_state);
+        let mut annotations = record.annotations.into_iter();
+        game.set_annotations(annotations.next().unwrap_or_default());
+        for uci in record.plays {
+            let play = Play::from_uci(&uci, game.game_state()).map_err(D::Error::custom)?;
+            game.play(play);
+            game.set_annotations(annotations.next().unwrap_or_default());
+        }
+        if !game.go_to(record.ply) {
+            return Err(D::Error::custom("ply is past the last play"));
+        }
+        game.metadata = record.metadata;
+        return Ok(game);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_and_positions_as_strings() {
+        let tile = TilePosition {
+            letter: 4,
+            number: 3,
+        };
+        assert_eq!(serde_json::to_string(&tile).unwrap(), "\"e4\"");
+        assert_eq!(
+            serde_json::from_str::<TilePosition>("\"e4\"").unwrap(),
+            tile
+        );
+        assert!(serde_json::from_str::<TilePosition>("\"i9\"").is_err());
+
+        let game_state = GameState::new();
+        let json = serde_json::to_string(&game_state).unwrap();
+        assert_eq!(json, format!("\"{}\"", game_state.to_fen()));
+        assert_eq!(
+            serde_json::from_str::<GameState>(&json).unwrap(),
+            game_state
+        );
+    }
+
+    #[test]
+    fn plays_are_checked() {
+        let game_state = GameState::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        for play in Play::get_all_possible_plays(&game_state) {
+            let json = serde_json::to_string(&play).unwrap();
+            assert_eq!(serde_json::from_str::<Play>(&json).unwrap(), play);
+        }
+        let quiet_capture = r#"{"origin":"g1","target":"f3","kind":"Quiet","captured":{"piece":"Queen","team":"Black"}}"#;
+        assert!(serde_json::from_str::<Play>(quiet_capture).is_err());
+        let empty_capture = r#"{"origin":"e4","target":"d5","kind":"Capture","captured":null}"#;
+        assert!(serde_json::from_str::<Play>(empty_capture).is_err());
+    }
+
+    #[test]
+    fn game_round_trip() {
+        let mut game = Game::new();
+        for san in ["e4", "d5", "exd5", "Nf6", "Bb5+", "c6"] {
+            game.play(Play::from_san(san, game.game_state()).unwrap());
+        }
+        game.set_annotations(vec![Annotation::Circle {
+            tile: TilePosition::from_notation("c6").unwrap(),
+            color: crate::game::AnnotationColor::Red,
+        }]);
+        game.undo();
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(serde_json::from_str::<Game>(&json).unwrap(), game);
+    }
+
+    #[test]
+    fn game_with_impossible_play() {
+        let json = serde_json::to_string(&Game::new())
+            .unwrap()
+            .replace("\"plays\":[]", "\"plays\":[\"e2e5\"]");
+        assert!(serde_json::from_str::<Game>(&json).is_err());
+    }
+}
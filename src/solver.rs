@@ -1,14 +1,235 @@
-/*struct PossiblePlaysTreeNode {
-    game_state: GameState,
-    possibilities: Vec<PossiblePlaysTreeNode>,
-}
-fn get_value(&self) -> u32 {
-    match *self {
-        Piece::Pawn(_) => return 1,
-        Piece::Horse => return 3,
-        Piece::Bishop => return 3,
-        Piece::Rook(_) => return 5,
-        Piece::Queen => return 9,
-        Piece::King(_) => return 10_000,
-    }
-}*/
+use crate::chess::*;
+use std::time::{Duration, Instant};
+
+pub const MATE: i32 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: u32,
+    // stops deepening once the time is up, the last finished depth is used
+    pub time: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SearchResult {
+    pub play: Option<Play>,
+    // in centipawns from the view of the team to move
+    pub evaluation: i32,
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        return Self {
+            depth: 4,
+            time: None,
+        };
+    }
+}
+
+impl Piece {
+    pub fn get_value(&self) -> i32 {
+        match *self {
+            Piece::Pawn(_) => return 100,
+            Piece::Horse => return 300,
+            Piece::Bishop => return 300,
+            Piece::Rook(_) => return 500,
+            Piece::Queen => return 900,
+            Piece::King(_) => return 10_000,
+        }
+    }
+}
+
+struct Search {
+    deadline: Option<Instant>,
+    nodes: u64,
+    aborted: bool,
+}
+
+pub fn evaluate(game_state: &GameState) -> i32 {
+    let mut evaluation = 0;
+    for (number, row) in game_state.board.iter().enumerate() {
+        for (letter, piece_option) in row.iter().enumerate() {
+            let Some(piece_with_team) = *piece_option else {
+                continue;
+            };
+            // rows counted from the own side of the board
+            let advancement = match piece_with_team.team {
+                Team::White => number as i32,
+                Team::Black => 7 - number as i32,
+            };
+            let centrality = 3 - (2 * letter as i32 - 7).abs() / 2;
+            let bonus = match piece_with_team.piece {
+                Piece::Pawn(_) => advancement * 5 + centrality * 2,
+                Piece::Horse | Piece::Bishop => centrality * 5 + advancement.min(3) * 3,
+                Piece::Queen => centrality * 2,
+                Piece::Rook(_) | Piece::King(_) => 0,
+            };
+            let value = piece_with_team.piece.get_value() + bonus;
+            match piece_with_team.team == game_state.turn {
+                true => evaluation += value,
+                false => evaluation -= value,
+            }
+        }
+    }
+    return evaluation;
+}
+
+pub fn search(game_state: &GameState, limits: SearchLimits) -> SearchResult {
+    let mut search = Search {
+        deadline: limits.time.map(|time| Instant::now() + time),
+        nodes: 0,
+        aborted: false,
+    };
+    let mut result = SearchResult {
+        play: None,
+        evaluation: evaluate(game_state),
+        depth: 0,
+        nodes: 0,
+    };
+    let mut plays = Play::get_all_possible_plays(game_state);
+    if plays.is_empty() {
+        // no possible plays, the game is over
+        result.evaluation = match game_state.is_in_check(game_state.turn) {
+            true => -MATE,
+            false => 0,
+        };
+        return result;
+    }
+    order_plays(&mut plays);
+    for depth in 1..=limits.depth.max(1) {
+        if let Some(best) = result.play {
+            if let Some(index) = plays.iter().position(|play| *play == best) {
+                plays[..=index].rotate_right(1);
+            }
+        }
+
+        let mut alpha = -MATE - 1;
+        let mut best_play = None;
+        for play in plays.iter() {
+            let evaluation =
+                -search.negamax(&game_state.after(*play), depth - 1, 1, -MATE - 1, -alpha);
+            if search.aborted {
+                break;
+            }
+            if evaluation > alpha {
+                alpha = evaluation;
+                best_play = Some(*play);
+            }
+        }
+        // an aborted depth is only partly searched, so it is not trusted
+        if search.aborted {
+            break;
+        }
+        result.play = best_play;
+        result.evaluation = alpha;
+        result.depth = depth;
+        if alpha.abs() > MATE - 1000 {
+            break;
+        }
+    }
+    if result.play.is_none() {
+        // out of time before the first depth was done
+        result.play = plays.first().copied();
+    }
+    result.nodes = search.nodes;
+    return result;
+}
+
+fn order_plays(plays: &mut [Play]) {
+    // most valuable victim first, promotions count as winning the new piece
+    plays.sort_by_key(|play| {
+        let mut gain = match play.captured {
+            Some(captured) => captured.piece.get_value(),
+            None => 0,
+        };
+        if let PlayKind::Promotion(piece) = play.kind {
+            gain += piece.get_value();
+        }
+        -gain
+    });
+}
+
+impl Search {
+    fn out_of_time(&mut self) -> bool {
+        if self.nodes.is_multiple_of(1024) {
+            if let Some(deadline) = self.deadline {
+                if Instant::now() >= deadline {
+                    self.aborted = true;
+                }
+            }
+        }
+        return self.aborted;
+    }
+    fn negamax(
+        &mut self,
+        game_state: &GameState,
+        depth: u32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.out_of_time() {
+            return 0;
+        }
+        if depth == 0 {
+            return self.quiescence(game_state, alpha, beta);
+        }
+        let mut plays = Play::get_all_possible_plays(game_state);
+        if plays.is_empty() {
+            return match game_state.is_in_check(game_state.turn) {
+                true => -MATE + ply,
+                false => 0,
+            };
+        }
+        order_plays(&mut plays);
+        for play in plays {
+            let evaluation =
+                -self.negamax(&game_state.after(play), depth - 1, ply + 1, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if evaluation >= beta {
+                return beta;
+            }
+            if evaluation > alpha {
+                alpha = evaluation;
+            }
+        }
+        return alpha;
+    }
+    // only follows captures, so the search does not stop in the middle of a trade
+    fn quiescence(&mut self, game_state: &GameState, mut alpha: i32, beta: i32) -> i32 {
+        let standing = evaluate(game_state);
+        if standing >= beta {
+            return beta;
+        }
+        if standing > alpha {
+            alpha = standing;
+        }
+        let mut plays: Vec<Play> = Play::get_all_possible_plays(game_state)
+            .into_iter()
+            .filter(|play| play.is_capture() || matches!(play.kind, PlayKind::Promotion(_)))
+            .collect();
+        order_plays(&mut plays);
+        for play in plays {
+            self.nodes += 1;
+            if self.out_of_time() {
+                return 0;
+            }
+            let evaluation = -self.quiescence(&game_state.after(play), -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if evaluation >= beta {
+                return beta;
+            }
+            if evaluation > alpha {
+                alpha = evaluation;
+            }
+        }
+        return alpha;
+    }
+}
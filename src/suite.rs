@@ -0,0 +1,149 @@
+use crate::chess::*;
+use crate::epd::Epd;
+use crate::notation::NotationError;
+use crate::solver::{self, SearchLimits, SearchResult};
+use std::time::{Duration, Instant};
+
+// how the solver did on one position of a test suite like wac or sts
+#[derive(Clone, Debug)]
+pub struct PositionResult {
+    // from the id operation
+    pub id: Option<String>,
+    // from the c0 operation
+    pub comment: Option<String>,
+    // the plays of bm and am, one of the first and none of the second solves the position
+    pub best_plays: Vec<Play>,
+    pub avoid_plays: Vec<Play>,
+    pub search: SearchResult,
+    pub solved: bool,
+    pub time: Duration,
+}
+
+// how many positions of a suite were solved, failed or could not be read
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SuiteScore {
+    pub solved: u32,
+    pub failed: u32,
+    pub errors: u32,
+}
+
+impl SuiteScore {
+    pub fn positions(&self) -> u32 {
+        return self.solved + self.failed + self.errors;
+    }
+    pub fn add(&mut self, result: &Result<(Epd, PositionResult), NotationError>) {
+        match result {
+            Ok((_, result)) if result.solved => self.solved += 1,
+            Ok(_) => self.failed += 1,
+            Err(_) => self.errors += 1,
+        }
+    }
+}
+
+// searches the position of the epd and compares the answer to its bm and am operations
+pub fn solve(epd: &Epd, limits: SearchLimits) -> Result<PositionResult, NotationError> {
+    let plays = |opcode: &str| -> Result<Vec<Play>, NotationError> {
+        let operands = epd.operation(opcode).unwrap_or_default();
+        return operands
+            .iter()
+            .map(|notation| Play::from_notation(notation, &epd.game_state))
+            .collect();
+    };
+    let best_plays = plays("bm")?;
+    let avoid_plays = plays("am")?;
+    if best_plays.is_empty() && avoid_plays.is_empty() {
+        return Err(NotationError::Epd(
+            "the position has no bm or am operation".to_string(),
+        ));
+    }
+
+    let start = Instant::now();
+    let search = solver::search(&epd.game_state, limits);
+    let time = start.elapsed();
+    let solved = match search.play {
+        Some(play) => {
+            (best_plays.is_empty() || best_plays.contains(&play)) && !avoid_plays.contains(&play)
+        }
+        None => false,
+    };
+    let text = |opcode: &str| epd.operation(opcode).map(|operands| operands.join(" "));
+    return Ok(PositionResult {
+        id: text("id"),
+        comment: text("c0"),
+        best_plays,
+        avoid_plays,
+        search,
+        solved,
+        time,
+    });
+}
+
+// every position of an epd file with the number of its line, searched one at a time
+// so the results can be shown while the rest is still searched
+pub fn solve_all(
+    text: &str,
+    limits: SearchLimits,
+) -> impl Iterator<Item = (usize, Result<(Epd, PositionResult), NotationError>)> + '_ {
+    return text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(move |(index, line)| {
+            let result =
+                Epd::from_epd(line).and_then(|epd| Ok((epd.clone(), solve(&epd, limits)?)));
+            return (index + 1, result);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATE_IN_ONE: &str = "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - -";
+
+    #[test]
+    fn suite() {
+        let text = format!(
+            "{position} bm Ra8#; id \"back row\";\n\n{position} am Ra8#; c0 \"the mate is forbidden\";\nnot an epd\n{position} id \"nothing to check\";\n",
+            position = MATE_IN_ONE
+        );
+        let limits = SearchLimits {
+            depth: 2,
+            time: None,
+        };
+        let mut score = SuiteScore::default();
+        let mut lines = vec![];
+        for (line, result) in solve_all(&text, limits) {
+            score.add(&result);
+            lines.push(line);
+            match line {
+                1 => {
+                    let (_, result) = result.unwrap();
+                    assert!(result.solved);
+                    assert_eq!(result.id.as_deref(), Some("back row"));
+                }
+                3 => {
+                    let (epd, result) = result.unwrap();
+                    assert!(!result.solved);
+                    assert_eq!(
+                        result.avoid_plays,
+                        [Play::from_san("Ra8#", &epd.game_state).unwrap()]
+                    );
+                    assert_eq!(result.comment.as_deref(), Some("the mate is forbidden"));
+                }
+                _ => assert!(result.is_err()),
+            }
+        }
+        // the empty line is left out
+        assert_eq!(lines, [1, 3, 4, 5]);
+        assert_eq!(
+            score,
+            SuiteScore {
+                solved: 1,
+                failed: 1,
+                errors: 2,
+            }
+        );
+        assert_eq!(score.positions(), 4);
+    }
+}
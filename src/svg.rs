@@ -0,0 +1,387 @@
+use crate::chess::*;
+use crate::game::{Annotation, AnnotationColor};
+use crate::pictures::{
+    arrow_outline, coordinate_color, distinct_tiles, piece_picture, CIRCLE_RADIUS, CIRCLE_WIDTH,
+    HIGHLIGHT,
+};
+use std::path::PathBuf;
+
+// the colors of the embedded board picture
+const LIGHT: &str = "#ffffff";
+const DARK: &str = "#372a27";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PieceSet {
+    // the pictures of the window, as pngs inside the svg
+    Embedded,
+    // the unicode chess symbols, drawn with whatever font shows the svg
+    Unicode,
+    // Chess_klt45.svg and the others, named like the files in png/
+    Directory(PathBuf),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SvgOptions {
+    // width and height in pixels, the drawing itself scales freely
+    pub size: u32,
+    // black at the bottom
+    pub flipped: bool,
+    // letters along the bottom and numbers along the left edge
+    pub coordinates: bool,
+    // like the last play in the window
+    pub highlights: Vec<TilePosition>,
+    pub annotations: Vec<Annotation>,
+    pub pieces: PieceSet,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        return Self {
+            size: 400,
+            flipped: false,
+            coordinates: true,
+            highlights: vec![],
+            annotations: vec![],
+            pieces: PieceSet::Embedded,
+        };
+    }
+}
+
+impl GameState {
+    // one unit is one tile, only reading the piece set can fail
+    pub fn to_svg(&self, options: &SvgOptions) -> std::io::Result<String> {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 8 8\">\n",
+            size = options.size
+        );
+        // a picture of every piece that is on the board, used once per piece
+        if options.pieces != PieceSet::Unicode {
+            svg += "<defs>\n";
+            let mut defined: Vec<char> = vec![];
+            for piece_with_team in self.board.iter().flatten().flatten() {
+                let id = piece_with_team.to_char();
+                if defined.contains(&id) {
+                    continue;
+                }
+                defined.push(id);
+                let (file_name, embedded) = piece_picture(*piece_with_team);
+                let href = match &options.pieces {
+                    PieceSet::Directory(directory) => {
+                        let path = directory.join(file_name).with_extension("svg");
+                        let bytes = std::fs::read(&path).map_err(|error| {
+                            std::io::Error::new(
+                                error.kind(),
+                                format!("{}: {}", path.display(), error),
+                            )
+                        })?;
+                        format!("data:image/svg+xml;base64,{}", base64(&bytes))
+                    }
+                    _ => format!("data:image/png;base64,{}", base64(embedded)),
+                };
+                svg += &format!(
+                    "<image id=\"{}\" width=\"1\" height=\"1\" href=\"{}\"/>\n",
+                    piece_id(*piece_with_team),
+                    href
+                );
+            }
+            svg += "</defs>\n";
+        }
+
+        svg += &format!("<rect width=\"8\" height=\"8\" fill=\"{}\"/>\n", LIGHT);
+        for number in 0..8 {
+            for letter in 0..8 {
+                if (letter + number) % 2 != 0 {
+                    continue;
+                }
+                let [x, y] = corner(TilePosition { letter, number }, options.flipped);
+                svg += &format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\" fill=\"{}\"/>\n",
+                    x, y, DARK
+                );
+            }
+        }
+        for tile in distinct_tiles(&options.highlights) {
+            let [x, y] = corner(tile, options.flipped);
+            svg += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\" {}/>\n",
+                x,
+                y,
+                fill(HIGHLIGHT)
+            );
+        }
+        if options.coordinates {
+            svg += &coordinates(options.flipped);
+        }
+
+        for (number, row) in self.board.iter().enumerate() {
+            for (letter, piece_option) in row.iter().enumerate() {
+                let Some(piece_with_team) = *piece_option else {
+                    continue;
+                };
+                let [x, y] = corner(TilePosition { letter, number }, options.flipped);
+                match options.pieces {
+                    PieceSet::Unicode => {
+                        svg += &format!(
+                            "<text x=\"{}\" y=\"{}\" font-size=\"0.85\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                            x as f32 + 0.5,
+                            y as f32 + 0.5,
+                            piece_with_team.to_figurine()
+                        );
+                    }
+                    _ => {
+                        svg += &format!(
+                            "<use href=\"#{}\" x=\"{}\" y=\"{}\"/>\n",
+                            piece_id(piece_with_team),
+                            x,
+                            y
+                        );
+                    }
+                }
+            }
+        }
+
+        for annotation in &options.annotations {
+            svg += &annotation_svg(annotation, options.flipped);
+        }
+        svg += "</svg>\n";
+        return Ok(svg);
+    }
+}
+
+// ids like "white-king", readable in the svg
+fn piece_id(piece_with_team: PieceWithTeam) -> String {
+    let team = match piece_with_team.team {
+        Team::White => "white",
+        Team::Black => "black",
+    };
+    let piece = match piece_with_team.piece {
+        Piece::King(_) => "king",
+        Piece::Queen => "queen",
+        Piece::Rook(_) => "rook",
+        Piece::Bishop => "bishop",
+        Piece::Horse => "horse",
+        Piece::Pawn(_) => "pawn",
+    };
+    return format!("{}-{}", team, piece);
+}
+
+// the top left corner of the tile, the 8th row is at the top
+fn corner(tile: TilePosition, flipped: bool) -> [usize; 2] {
+    let tile = tile.oriented(flipped);
+    return [tile.letter, 7 - tile.number];
+}
+
+fn center(tile: TilePosition, flipped: bool) -> [f32; 2] {
+    let [x, y] = corner(tile, flipped);
+    return [x as f32 + 0.5, y as f32 + 0.5];
+}
+
+// the letters along the bottom and the numbers along the left of the board as it is seen
+fn coordinates(flipped: bool) -> String {
+    let mut svg =
+        String::from("<g font-family=\"monospace\" font-size=\"0.2\" font-weight=\"bold\">\n");
+    for index in 0..8 {
+        let bottom = TilePosition {
+            letter: index,
+            number: 0,
+        }
+        .oriented(flipped);
+        let left = TilePosition {
+            letter: 0,
+            number: index,
+        }
+        .oriented(flipped);
+        svg += &format!(
+            "<text x=\"{}\" y=\"7.95\" text-anchor=\"end\" {}>{}</text>\n",
+            index as f32 + 0.95,
+            fill(coordinate_color(bottom)),
+            (b'a' + bottom.letter as u8) as char
+        );
+        svg += &format!(
+            "<text x=\"0.05\" y=\"{}\" {}>{}</text>\n",
+            (7 - index) as f32 + 0.2,
+            fill(coordinate_color(left)),
+            left.number + 1
+        );
+    }
+    svg += "</g>\n";
+    return svg;
+}
+
+// the fill attributes of an rgba color
+fn fill(color: [u8; 4]) -> String {
+    return format!(
+        "fill=\"#{:02x}{:02x}{:02x}\" fill-opacity=\"{}\"",
+        color[0],
+        color[1],
+        color[2],
+        decimal(color[3] as f32 / 255.0)
+    );
+}
+
+fn annotation_svg(annotation: &Annotation, flipped: bool) -> String {
+    let color = match annotation.color() {
+        AnnotationColor::Green => "#15781f",
+        AnnotationColor::Red => "#870000",
+        AnnotationColor::Yellow => "#e6a100",
+        AnnotationColor::Blue => "#003087",
+    };
+    match *annotation {
+        Annotation::Arrow { origin, target, .. } => {
+            let outline = arrow_outline(center(origin, flipped), center(target, flipped), 1.0);
+            let points: Vec<String> = outline
+                .iter()
+                .map(|[x, y]| format!("{},{}", decimal(*x), decimal(*y)))
+                .collect();
+            return format!(
+                "<polygon points=\"{}\" fill=\"{}\" fill-opacity=\"0.8\"/>\n",
+                points.join(" "),
+                color
+            );
+        }
+        Annotation::Circle { tile, .. } => {
+            let [x, y] = center(tile, flipped);
+            return format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-opacity=\"0.8\"/>\n",
+                x,
+                y,
+                // the stroke is centered on the radius
+                decimal(CIRCLE_RADIUS - CIRCLE_WIDTH * 0.5),
+                color,
+                CIRCLE_WIDTH
+            );
+        }
+    }
+}
+
+// short numbers, svg does not need more than a thousandth of a tile
+fn decimal(value: f32) -> String {
+    let text = format!("{:.3}", value);
+    return text.trim_end_matches('0').trim_end_matches('.').to_string();
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => text.push(BASE64[(value >> (18 - index * 6) & 63) as usize] as char),
+                false => text.push('='),
+            }
+        }
+    }
+    return text;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(notation: &str) -> TilePosition {
+        return TilePosition::from_notation(notation).unwrap();
+    }
+
+    #[test]
+    fn base64_vectors() {
+        // from rfc 4648, with every length of padding
+        for (bytes, text) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64(bytes.as_bytes()), text);
+        }
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+    }
+
+    #[test]
+    fn start_position() {
+        let options = SvgOptions {
+            coordinates: false,
+            ..SvgOptions::default()
+        };
+        let svg = GameState::new().to_svg(&options).unwrap();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        // the light background and the 32 dark tiles
+        assert_eq!(svg.matches("<rect ").count(), 33);
+        assert_eq!(svg.matches(&format!("fill=\"{}\"", DARK)).count(), 32);
+        // one picture per kind of piece, used for every piece
+        assert_eq!(svg.matches("<image ").count(), 12);
+        assert_eq!(svg.matches("<use ").count(), 32);
+        assert!(svg.contains("<use href=\"#white-king\" x=\"4\" y=\"7\"/>"));
+        assert!(svg.contains("<use href=\"#black-queen\" x=\"3\" y=\"0\"/>"));
+        assert!(!svg.contains("<text"));
+
+        let unicode = GameState::new()
+            .to_svg(&SvgOptions {
+                pieces: PieceSet::Unicode,
+                ..options
+            })
+            .unwrap();
+        assert!(!unicode.contains("<defs>"));
+        assert_eq!(unicode.matches("<text ").count(), 32);
+    }
+
+    #[test]
+    fn flipped() {
+        let options = SvgOptions {
+            flipped: true,
+            ..SvgOptions::default()
+        };
+        let svg = GameState::new().to_svg(&options).unwrap();
+        assert!(svg.contains("<use href=\"#white-king\" x=\"3\" y=\"0\"/>"));
+        assert!(svg.contains("<use href=\"#black-queen\" x=\"4\" y=\"7\"/>"));
+        // h is in the bottom left corner and 8 at the bottom of the left edge
+        assert!(svg.contains(">h</text>\n<text x=\"0.05\" y=\"7.2\""));
+        assert!(svg.contains("y=\"7.2\" fill=\"#f2f2f2\" fill-opacity=\"0.851\">8</text>"));
+    }
+
+    #[test]
+    fn last_play() {
+        let game_state = GameState::new();
+        let play = Play::from_san("e4", &game_state).unwrap();
+        let options = SvgOptions {
+            coordinates: false,
+            highlights: vec![play.origin, play.target],
+            annotations: vec![
+                Annotation::Arrow {
+                    origin: tile("g1"),
+                    target: tile("f3"),
+                    color: AnnotationColor::Green,
+                },
+                Annotation::Circle {
+                    tile: tile("e5"),
+                    color: AnnotationColor::Red,
+                },
+            ],
+            ..SvgOptions::default()
+        };
+        let svg = game_state.after(play).to_svg(&options).unwrap();
+        let highlight = fill(HIGHLIGHT);
+        assert_eq!(svg.matches(&highlight).count(), 2);
+        assert!(svg.contains(&format!(
+            "<rect x=\"4\" y=\"6\" width=\"1\" height=\"1\" {}/>",
+            highlight
+        )));
+        assert!(svg.contains(&format!(
+            "<rect x=\"4\" y=\"4\" width=\"1\" height=\"1\" {}/>",
+            highlight
+        )));
+        assert!(svg.contains("<use href=\"#white-pawn\" x=\"4\" y=\"4\"/>"));
+        assert_eq!(svg.matches("<polygon ").count(), 1);
+        assert!(svg.contains("fill=\"#15781f\""));
+        assert!(svg.contains("<circle cx=\"4.5\" cy=\"3.5\""));
+        // the annotations are drawn above the pieces
+        assert!(svg.rfind("<use ").unwrap() < svg.find("<polygon ").unwrap());
+    }
+}
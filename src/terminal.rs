@@ -0,0 +1,282 @@
+use crate::chess::*;
+use crate::game::{Game, GameResult};
+use crate::solver::{self, SearchLimits, MATE};
+use std::fmt;
+use std::io::{BufRead, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayOptions {
+    // figurines like ♘ instead of letters like N
+    pub unicode: bool,
+    // ansi escape codes for the tile and piece colors
+    pub colors: bool,
+    pub coordinates: bool,
+}
+
+pub struct BoardDisplay<'a> {
+    game_state: &'a GameState,
+    options: DisplayOptions,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        return Self {
+            unicode: false,
+            colors: false,
+            coordinates: true,
+        };
+    }
+}
+
+impl PieceWithTeam {
+    pub fn to_figurine(&self) -> char {
+        match (self.team, self.piece) {
+            (Team::White, Piece::King(_)) => '♔',
+            (Team::White, Piece::Queen) => '♕',
+            (Team::White, Piece::Rook(_)) => '♖',
+            (Team::White, Piece::Bishop) => '♗',
+            (Team::White, Piece::Horse) => '♘',
+            (Team::White, Piece::Pawn(_)) => '♙',
+            (Team::Black, Piece::King(_)) => '♚',
+            (Team::Black, Piece::Queen) => '♛',
+            (Team::Black, Piece::Rook(_)) => '♜',
+            (Team::Black, Piece::Bishop) => '♝',
+            (Team::Black, Piece::Horse) => '♞',
+            (Team::Black, Piece::Pawn(_)) => '♟',
+        }
+    }
+}
+
+impl GameState {
+    pub fn display(&self, options: DisplayOptions) -> BoardDisplay<'_> {
+        return BoardDisplay {
+            game_state: self,
+            options,
+        };
+    }
+}
+
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display(DisplayOptions::default()))
+    }
+}
+
+impl fmt::Display for BoardDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for number in (0..8).rev() {
+            if self.options.coordinates {
+                write!(f, "{} ", number + 1)?;
+            }
+            for letter in 0..8 {
+                let piece_option = self.game_state.board[number][letter];
+                let symbol = match piece_option {
+                    // with colors the filled figurines read better for both teams
+                    Some(piece_with_team) if self.options.unicode && self.options.colors => {
+                        PieceWithTeam {
+                            piece: piece_with_team.piece,
+                            team: Team::Black,
+                        }
+                        .to_figurine()
+                    }
+                    Some(piece_with_team) if self.options.unicode => piece_with_team.to_figurine(),
+                    Some(piece_with_team) => piece_with_team.to_char(),
+                    None if self.options.colors => ' ',
+                    None if self.options.unicode => '·',
+                    None => '.',
+                };
+                if !self.options.colors {
+                    match letter {
+                        7 => write!(f, "{}", symbol)?,
+                        _ => write!(f, "{} ", symbol)?,
+                    }
+                    continue;
+                }
+                let background = match (letter + number) % 2 {
+                    0 => 137,
+                    _ => 223,
+                };
+                let foreground = match piece_option.map(|piece_with_team| piece_with_team.team) {
+                    Some(Team::White) => 231,
+                    _ => 16,
+                };
+                write!(
+                    f,
+                    "\x1b[48;5;{}m\x1b[38;5;{}m {} \x1b[0m",
+                    background, foreground, symbol
+                )?;
+            }
+            writeln!(f)?;
+        }
+        if self.options.coordinates {
+            match self.options.colors {
+                true => writeln!(f, "   a  b  c  d  e  f  g  h")?,
+                false => writeln!(f, "  a b c d e f g h")?,
+            }
+        }
+        match self.game_state.turn {
+            Team::White => write!(f, "white to move"),
+            Team::Black => write!(f, "black to move"),
+        }
+    }
+}
+
+const HELP: &str = "\
+plays are typed in san (Nf3, exd5, O-O, e8=Q) or uci (g1f3)
+  moves        list the possible plays
+  go [depth]   let the solver play
+  undo         take back the last play
+  redo         play the taken back play again
+  fen [fen]    print the position or set up a new one
+  pgn          print the game
+  new          start a new game
+  help         show this text
+  quit         leave";
+
+pub fn run<R: BufRead, W: Write>(
+    input: R,
+    mut output: W,
+    options: DisplayOptions,
+) -> std::io::Result<()> {
+    let mut game = Game::new();
+    let mut limits = SearchLimits::default();
+
+    writeln!(output, "{}", game.game_state().display(options))?;
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            write!(output, "> ")?;
+            output.flush()?;
+            continue;
+        };
+        let game_state = *game.game_state();
+        let mut show_board = false;
+        match command {
+            "quit" | "exit" => return Ok(()),
+            "help" => writeln!(output, "{}", HELP)?,
+            "moves" => {
+                let plays: Vec<String> = Play::get_all_possible_plays(&game_state)
+                    .iter()
+                    .map(|play| play.to_san(&game_state))
+                    .collect();
+                writeln!(output, "{}", plays.join(" "))?;
+            }
+            "undo" => match game.undo() {
+                true => show_board = true,
+                false => writeln!(output, "nothing to undo")?,
+            },
+            "redo" => match game.redo() {
+                true => show_board = true,
+                false => writeln!(output, "nothing to redo")?,
+            },
+            "new" => {
+                game = Game::new();
+                show_board = true;
+            }
+            "fen" => {
+                let fen: Vec<&str> = words.collect();
+                if fen.is_empty() {
+                    writeln!(output, "{}", game.to_fen())?;
+                } else {
+                    match Game::from_fen(&fen.join(" ")) {
+                        Ok(new_game) => {
+                            game = new_game;
+                            show_board = true;
+                        }
+                        Err(error) => writeln!(output, "{}", error)?,
+                    }
+                }
+            }
+            "pgn" => write!(output, "{}", game.to_pgn())?,
+            "go" => match words.next().map(|depth| depth.parse()) {
+                Some(Err(_)) => writeln!(output, "depth must be a number")?,
+                depth => {
+                    if let Some(Ok(depth)) = depth {
+                        limits.depth = depth;
+                    }
+                    let result = solver::search(&game_state, limits);
+                    match result.play {
+                        Some(play) => {
+                            writeln!(
+                                output,
+                                "{} ({}, depth {}, {} nodes)",
+                                play.to_san(&game_state),
+                                format_evaluation(result.evaluation),
+                                result.depth,
+                                result.nodes
+                            )?;
+                            game.play(play);
+                            show_board = true;
+                        }
+                        None => writeln!(output, "there is nothing to play")?,
+                    }
+                }
+            },
+            notation => match Play::from_notation(notation, &game_state) {
+                Ok(play) => {
+                    game.play(play);
+                    show_board = true;
+                }
+                Err(error) => writeln!(output, "{}, type help for the commands", error)?,
+            },
+        }
+        if show_board {
+            let game_state = game.game_state();
+            writeln!(output, "{}", game_state.display(options))?;
+            match GameResult::of(game_state) {
+                GameResult::WhiteWins => writeln!(output, "checkmate, white wins")?,
+                GameResult::BlackWins => writeln!(output, "checkmate, black wins")?,
+                GameResult::Draw => writeln!(output, "stalemate")?,
+                GameResult::Ongoing => {}
+            }
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    return Ok(());
+}
+
+pub fn format_evaluation(evaluation: i32) -> String {
+    if evaluation.abs() > MATE - 1000 {
+        // plies until mate, rounded up to full moves
+        let moves = (MATE - evaluation.abs() + 1) / 2;
+        return match evaluation > 0 {
+            true => format!("#{}", moves),
+            false => format!("#-{}", moves),
+        };
+    }
+    return format!("{:+.2}", evaluation as f32 / 100.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session() {
+        let input = "undo\ne4\nundo\nredo\nfen\nQh5\nquit\nd5\n";
+        let mut output = vec![];
+        run(input.as_bytes(), &mut output, DisplayOptions::default()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let answers: Vec<&str> = output.split("> ").collect();
+        // the board at the start and one prompt per line up to quit
+        assert_eq!(answers.len(), 8);
+        assert!(answers[0].ends_with("white to move\n"));
+        assert_eq!(answers[1], "nothing to undo\n");
+        assert!(answers[2].ends_with("black to move\n"));
+        assert!(answers[2].contains("4 . . . . P . . ."));
+        assert!(answers[3].ends_with("white to move\n"));
+        assert!(answers[4].ends_with("black to move\n"));
+        assert_eq!(
+            answers[5],
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1\n"
+        );
+        assert!(answers[6].ends_with(", type help for the commands\n"));
+        // nothing is read after quit
+        assert_eq!(answers[7], "");
+    }
+}
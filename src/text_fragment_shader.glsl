@@ -0,0 +1,11 @@
+#version 140
+
+in vec2 v_texture_position;
+out vec4 color;
+
+uniform sampler2D tex;
+uniform vec4 tint;
+
+void main() {
+  color = tint*texture(tex, v_texture_position);
+}
@@ -0,0 +1,14 @@
+#version 150
+
+in vec2 position;
+in vec2 texture_position;
+out vec2 v_texture_position;
+
+uniform mat4 matrix;
+// offset and size of the glyph in the font texture
+uniform vec4 texture_rect;
+
+void main() {
+  v_texture_position = texture_rect.xy + texture_position*texture_rect.zw;
+  gl_Position = matrix*vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,248 @@
+use crate::chess::*;
+use std::fmt;
+
+// why a game state could never come up in a game
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PositionProblem {
+    NoKing(Team),
+    // the team and how many kings it has
+    TooManyKings(Team, usize),
+    // pawns promote on the last row and never go back to the first
+    PawnOnLastRow(TilePosition),
+    // a king that can still do a rochade has never moved from e1 or e8
+    RochadeKingNotHome(TilePosition),
+    // the same for the rooks in the corners
+    RochadeRookNotHome(TilePosition),
+    // its king could be taken right away
+    NotToMoveInCheck(Team),
+}
+
+impl fmt::Display for PositionProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionProblem::NoKing(team) => write!(f, "{} has no king", team_name(*team)),
+            PositionProblem::TooManyKings(team, count) => {
+                write!(f, "{} has {} kings", team_name(*team), count)
+            }
+            PositionProblem::PawnOnLastRow(tile) => {
+                write!(f, "pawn on {} in the first or last row", tile)
+            }
+            PositionProblem::RochadeKingNotHome(tile) => {
+                write!(f, "king on {} can not do a rochade anymore", tile)
+            }
+            PositionProblem::RochadeRookNotHome(tile) => {
+                write!(f, "rook on {} can not do a rochade anymore", tile)
+            }
+            PositionProblem::NotToMoveInCheck(team) => write!(
+                f,
+                "{} is in check with {} to move",
+                team_name(*team),
+                team_name(!*team)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PositionProblem {}
+
+fn team_name(team: Team) -> &'static str {
+    match team {
+        Team::White => return "white",
+        Team::Black => return "black",
+    }
+}
+
+impl GameState {
+    // everything that is wrong with the position, nothing for a playable one
+    pub fn validate(&self) -> Vec<PositionProblem> {
+        let mut problems = vec![];
+        for team in [Team::White, Team::Black] {
+            let kings = self
+                .board
+                .iter()
+                .flatten()
+                .flatten()
+                .filter(|piece_with_team| {
+                    piece_with_team.team == team && matches!(piece_with_team.piece, Piece::King(_))
+                })
+                .count();
+            match kings {
+                0 => problems.push(PositionProblem::NoKing(team)),
+                1 => {}
+                _ => problems.push(PositionProblem::TooManyKings(team, kings)),
+            }
+        }
+        for (number, row) in self.board.iter().enumerate() {
+            for (letter, piece_option) in row.iter().enumerate() {
+                let Some(piece_with_team) = *piece_option else {
+                    continue;
+                };
+                let tile = TilePosition { letter, number };
+                let home_number = match piece_with_team.team {
+                    Team::White => 0,
+                    Team::Black => 7,
+                };
+                match piece_with_team.piece {
+                    Piece::Pawn(_) if number == 0 || number == 7 => {
+                        problems.push(PositionProblem::PawnOnLastRow(tile))
+                    }
+                    Piece::King(RochadeAbility::Able) if number != home_number || letter != 4 => {
+                        problems.push(PositionProblem::RochadeKingNotHome(tile))
+                    }
+                    Piece::Rook(RochadeAbility::Able)
+                        if number != home_number || (letter != 0 && letter != 7) =>
+                    {
+                        problems.push(PositionProblem::RochadeRookNotHome(tile))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        // with two kings the check would only be looked for at one of them
+        if !problems
+            .iter()
+            .any(|problem| matches!(problem, PositionProblem::TooManyKings(..)))
+            && self.is_in_check(!self.turn)
+        {
+            problems.push(PositionProblem::NotToMoveInCheck(!self.turn));
+        }
+        return problems;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(notation: &str) -> TilePosition {
+        return TilePosition::from_notation(notation).unwrap();
+    }
+
+    fn place(game_state: &mut GameState, notation: &str, piece: Option<(Piece, Team)>) {
+        let tile = tile(notation);
+        game_state.board[tile.number][tile.letter] =
+            piece.map(|(piece, team)| PieceWithTeam { piece, team });
+    }
+
+    #[test]
+    fn playable_positions() {
+        assert_eq!(GameState::new().validate(), []);
+        let mut game_state = GameState::empty();
+        place(
+            &mut game_state,
+            "a1",
+            Some((Piece::King(RochadeAbility::Unable), Team::White)),
+        );
+        place(
+            &mut game_state,
+            "h8",
+            Some((Piece::King(RochadeAbility::Unable), Team::Black)),
+        );
+        assert_eq!(game_state.validate(), []);
+    }
+
+    #[test]
+    fn no_king() {
+        let mut game_state = GameState::new();
+        place(&mut game_state, "e8", None);
+        assert_eq!(
+            game_state.validate(),
+            [PositionProblem::NoKing(Team::Black)]
+        );
+    }
+
+    #[test]
+    fn too_many_kings() {
+        let mut game_state = GameState::new();
+        place(
+            &mut game_state,
+            "e4",
+            Some((Piece::King(RochadeAbility::Unable), Team::White)),
+        );
+        assert_eq!(
+            game_state.validate(),
+            [PositionProblem::TooManyKings(Team::White, 2)]
+        );
+    }
+
+    #[test]
+    fn pawn_on_last_row() {
+        let mut game_state = GameState::new();
+        place(
+            &mut game_state,
+            "b1",
+            Some((
+                Piece::Pawn(EnPassanteVulnerability::Invulnerable),
+                Team::White,
+            )),
+        );
+        place(
+            &mut game_state,
+            "b8",
+            Some((
+                Piece::Pawn(EnPassanteVulnerability::Invulnerable),
+                Team::White,
+            )),
+        );
+        assert_eq!(
+            game_state.validate(),
+            [
+                PositionProblem::PawnOnLastRow(tile("b1")),
+                PositionProblem::PawnOnLastRow(tile("b8"))
+            ]
+        );
+    }
+
+    #[test]
+    fn rochade_pieces_not_home() {
+        let mut game_state = GameState::new();
+        place(&mut game_state, "e1", None);
+        place(
+            &mut game_state,
+            "e2",
+            Some((Piece::King(RochadeAbility::Able), Team::White)),
+        );
+        assert_eq!(
+            game_state.validate(),
+            [PositionProblem::RochadeKingNotHome(tile("e2"))]
+        );
+
+        let mut game_state = GameState::new();
+        place(&mut game_state, "h8", None);
+        place(
+            &mut game_state,
+            "h6",
+            Some((Piece::Rook(RochadeAbility::Able), Team::Black)),
+        );
+        assert_eq!(
+            game_state.validate(),
+            [PositionProblem::RochadeRookNotHome(tile("h6"))]
+        );
+        // a white rook in black's corner is not home either
+        let mut game_state = GameState::new();
+        place(
+            &mut game_state,
+            "a8",
+            Some((Piece::Rook(RochadeAbility::Able), Team::White)),
+        );
+        assert_eq!(
+            game_state.validate(),
+            [PositionProblem::RochadeRookNotHome(tile("a8"))]
+        );
+    }
+
+    #[test]
+    fn not_to_move_in_check() {
+        let mut game_state = GameState::new();
+        place(&mut game_state, "f7", None);
+        place(&mut game_state, "h5", Some((Piece::Queen, Team::White)));
+        // fine while black can answer the check
+        game_state.turn = Team::Black;
+        assert_eq!(game_state.validate(), []);
+        game_state.turn = Team::White;
+        assert_eq!(
+            game_state.validate(),
+            [PositionProblem::NotToMoveInCheck(Team::Black)]
+        );
+    }
+}
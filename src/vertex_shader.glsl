@@ -3,10 +3,16 @@
 in vec2 position;
 in vec2 texture_position;
 out vec2 v_texture_position;
+out float v_opacity;
 
-uniform mat4 matrix;
+// per instance
+in vec2 offset;
+in vec2 scale;
+in vec4 atlas_rect;
+in float opacity;
 
 void main() {
-  v_texture_position = texture_position;
-  gl_Position = matrix*vec4(position, 0.0, 1.0);
+  v_texture_position = atlas_rect.xy + texture_position*atlas_rect.zw;
+  v_opacity = opacity;
+  gl_Position = vec4(offset + position*scale, 0.0, 1.0);
 }